@@ -4,7 +4,7 @@ use std::fs::create_dir;
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let out_dir = current_dir()?.join("src").join("apis");
     if !out_dir.exists() {
-        create_dir(&out_dir);
+        create_dir(&out_dir)?;
     }
 
     tonic_build::configure()
@@ -2369,6 +2369,8 @@ pub struct TransactionExtention {
     pub constant_result: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
     #[prost(message, optional, tag = "4")]
     pub result: ::core::option::Option<Return>,
+    #[prost(int64, tag = "5")]
+    pub energy_used: i64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct BlockExtention {
@@ -0,0 +1,440 @@
+//! 按 ABI 调用任意合约
+//!
+//! ABI 可以来自 Solidity 编译出的 JSON，也可以通过 `get_contract` 从链上读取。
+
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use ethabi::{Event, Function, Hash, ParamType, RawLog, Token};
+use serde_json::{json, Value};
+
+use crate::apis::smart_contract::abi::entry::{EntryType, Param, StateMutabilityType};
+use crate::apis::smart_contract::abi::Entry;
+use crate::apis::transaction_info::Log;
+use crate::apis::SmartContract;
+use crate::error::Error;
+use crate::key::Address;
+use crate::Result;
+use crate::services::{ServiceAgent, Transfer, TxOptions};
+use crate::signer::Signer;
+use crate::transaction::BroadcastReceipt;
+
+/// 绑定了地址和 ABI 的合约
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contract {
+    address: Address,
+    abi: ethabi::Contract,
+}
+
+impl Contract {
+    pub fn new(address: Address, abi: ethabi::Contract) -> Self {
+        Self { address, abi }
+    }
+
+    /// 由 JSON ABI 创建，格式见 [`parse_abi`]
+    pub fn from_json(address: Address, json: &str) -> Result<Self> {
+        Ok(Self { address, abi: parse_abi(json)? })
+    }
+
+    /// 由 `get_contract` 返回的合约创建
+    pub fn from_smart_contract(smart_contract: &SmartContract) -> Result<Self> {
+        let address = Address::try_from(&smart_contract.contract_address[..])?;
+        let entries = smart_contract.abi.iter()
+            .flat_map(|abi| &abi.entrys)
+            .map(|entry| normalize_entry(entry_json(entry)))
+            .collect();
+
+        Ok(Self { address, abi: abi_from_entries(entries)? })
+    }
+
+    /// 通过 `get_contract` 读取链上的 ABI
+    pub async fn load<S: Signer>(agent: &ServiceAgent<'_, S>, address: &Address) -> Result<Self> {
+        Self::from_smart_contract(&agent.get_contract(address).await?)
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn abi(&self) -> &ethabi::Contract {
+        &self.abi
+    }
+
+    /// 按名称查找函数，重载的函数需要用签名区分，如 `transfer(address,uint256)`，只给名称时返回 [`Error::OverloadedFunction`]
+    pub fn function(&self, name: &str) -> Result<&Function> {
+        let (function_name, params) = match name.find('(') {
+            Some(index) => (&name[..index], Some(&name[index..])),
+            None => (name, None),
+        };
+        let functions = self.abi.functions_by_name(function_name)?;
+
+        match params {
+            None if functions.len() > 1 => {
+                let signatures: Vec<_> = functions.iter().map(input_signature).collect();
+                Err(Error::OverloadedFunction(signatures.join(", ")))
+            }
+            None => Ok(&functions[0]),
+            Some(_) => functions.iter()
+                .find(|function| input_signature(function) == name)
+                .ok_or_else(|| ethabi::Error::InvalidName(name.into()).into()),
+        }
+    }
+
+    /// 编码函数调用，`address` 类型的参数可以传 base58 或十六进制的地址字符串
+    pub fn encode_input(&self, name: &str, tokens: &[Token]) -> Result<Vec<u8>> {
+        let function = self.function(name)?;
+        if tokens.len() != function.inputs.len() {
+            return Err(ethabi::Error::InvalidData.into());
+        }
+        let tokens = function.inputs.iter()
+            .zip(tokens.iter().cloned())
+            .map(|(param, token)| convert_addresses(&param.kind, token))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(function.encode_input(&tokens)?)
+    }
+
+    /// 以签名者为调用者执行只读调用并解码返回值
+    pub async fn call<S: Signer>(&self, agent: &ServiceAgent<'_, S>, name: &str, tokens: &[Token]) -> Result<Vec<Token>> {
+        let data = self.encode_input(name, tokens)?;
+        let output = agent.call_constant(&self.address, data).await?;
+
+        self.decode_output(name, &output)
+    }
+
+    /// 与 [`call`](Self::call) 相同，但在 solidity 节点已固化的状态上执行
+    pub async fn call_solidity<S: Signer>(&self, agent: &ServiceAgent<'_, S>, name: &str, tokens: &[Token]) -> Result<Vec<Token>> {
+        let data = self.encode_input(name, tokens)?;
+        let output = agent.call_constant_solidity(&self.address, data).await?;
+
+        self.decode_output(name, &output)
+    }
+
+    /// 按函数的输出参数解码返回数据
+    ///
+    /// 地址保留为 `Token::Address`，用 `Address::try_from` 转换为 TRON 地址。
+    pub fn decode_output(&self, name: &str, output: &[u8]) -> Result<Vec<Token>> {
+        Ok(self.function(name)?.decode_output(output)?)
+    }
+
+    /// 创建合约调用交易，签名并广播
+    pub async fn send<S: Signer>(
+        &self,
+        agent: &mut ServiceAgent<'_, S>,
+        name: &str,
+        tokens: &[Token],
+        options: &TxOptions,
+    ) -> Result<BroadcastReceipt> {
+        let data = self.encode_input(name, tokens)?;
+
+        agent.contract_transfer(&self.address, data, options).await
+    }
+}
+
+/// 解析 JSON ABI
+///
+/// 除 Solidity 的 ABI 数组外，也接受 TRON 节点 HTTP 接口返回的 `{"entrys": [...]}` 格式，
+/// 其中的类型名不区分大小写，`trcToken` 按 `uint256` 处理。
+pub fn parse_abi(json: &str) -> Result<ethabi::Contract> {
+    let value: Value = serde_json::from_str(json).map_err(ethabi::Error::from)?;
+    let entries = match value {
+        Value::Object(mut object) => object.remove("entrys").unwrap_or_default(),
+        value => value,
+    };
+
+    match entries {
+        Value::Array(entries) => abi_from_entries(entries.into_iter().map(normalize_entry).collect()),
+        _ => Err(ethabi::Error::InvalidData.into()),
+    }
+}
+
+/// 从 `TransactionInfo.log` 中解码事件参数，事件签名或数据不匹配时返回 `None`
+pub fn decode_log(event: &Event, log: &Log) -> Option<Vec<Token>> {
+    let raw_log = RawLog {
+        topics: log.topics.iter()
+            .map(|topic| (topic.len() == 32).then(|| Hash::from_slice(topic)))
+            .collect::<Option<_>>()?,
+        data: log.data.clone(),
+    };
+
+    Some(event.parse_log(raw_log).ok()?.params.into_iter().map(|param| param.value).collect())
+}
+
+fn abi_from_entries(entries: Vec<Value>) -> Result<ethabi::Contract> {
+    Ok(serde_json::from_value(Value::Array(entries)).map_err(ethabi::Error::from)?)
+}
+
+/// 把地址参数中的字符串解析为 TRON 地址
+fn convert_addresses(kind: &ParamType, token: Token) -> Result<Token> {
+    let convert_all = |kinds: &mut dyn Iterator<Item = &ParamType>, tokens: Vec<Token>| {
+        kinds.zip(tokens).map(|(kind, token)| convert_addresses(kind, token)).collect::<Result<Vec<_>>>()
+    };
+
+    Ok(match (kind, token) {
+        (ParamType::Address, Token::String(address)) => Address::from_str(&address)?.into(),
+        (ParamType::Array(kind), Token::Array(tokens)) => Token::Array(convert_all(&mut std::iter::repeat(&**kind), tokens)?),
+        (ParamType::FixedArray(kind, _), Token::FixedArray(tokens)) => {
+            Token::FixedArray(convert_all(&mut std::iter::repeat(&**kind), tokens)?)
+        }
+        (ParamType::Tuple(kinds), Token::Tuple(tokens)) => Token::Tuple(convert_all(&mut kinds.iter(), tokens)?),
+        (_, token) => token,
+    })
+}
+
+/// 不含输出参数的函数签名，如 `transfer(address,uint256)`
+fn input_signature(function: &Function) -> String {
+    let inputs: Vec<_> = function.inputs.iter().map(|param| param.kind.to_string()).collect();
+
+    format!("{}({})", function.name, inputs.join(","))
+}
+
+/// 把链上的 ABI 条目转换为 JSON 格式
+fn entry_json(entry: &Entry) -> Value {
+    let params = |params: &[Param]| -> Vec<Value> {
+        params.iter()
+            .map(|param| json!({ "name": param.name, "type": param.r#type, "indexed": param.indexed }))
+            .collect()
+    };
+    let r#type = match EntryType::from_i32(entry.r#type) {
+        Some(EntryType::Constructor) => "constructor",
+        Some(EntryType::Event) => "event",
+        Some(EntryType::Fallback) => "fallback",
+        Some(EntryType::Receive) => "receive",
+        _ => "function",
+    };
+    let state_mutability = match StateMutabilityType::from_i32(entry.state_mutability) {
+        Some(StateMutabilityType::Pure) => "pure",
+        Some(StateMutabilityType::View) => "view",
+        Some(StateMutabilityType::Payable) => "payable",
+        Some(StateMutabilityType::Nonpayable) => "nonpayable",
+        _ if entry.payable => "payable",
+        _ if entry.constant => "view",
+        _ => "nonpayable",
+    };
+
+    json!({
+        "type": r#type,
+        "name": entry.name,
+        "inputs": params(&entry.inputs),
+        "outputs": params(&entry.outputs),
+        "stateMutability": state_mutability,
+        "anonymous": entry.anonymous,
+    })
+}
+
+/// 补全 TRON ABI 中省略的字段，转换为 ethabi 能解析的格式
+fn normalize_entry(mut entry: Value) -> Value {
+    if let Value::Object(object) = &mut entry {
+        for key in ["type", "stateMutability"] {
+            if let Some(Value::String(value)) = object.get_mut(key) {
+                *value = value.to_lowercase();
+            }
+        }
+        object.entry("type").or_insert_with(|| "function".into());
+        object.entry("name").or_insert_with(|| "".into());
+        object.entry("anonymous").or_insert(Value::Bool(false));
+        for key in ["inputs", "outputs"] {
+            let params = object.entry(key).or_insert_with(|| Value::Array(vec![]));
+            if let Value::Array(params) = params {
+                params.iter_mut().for_each(normalize_param);
+            }
+        }
+    }
+
+    entry
+}
+
+fn normalize_param(param: &mut Value) {
+    if let Value::Object(object) = param {
+        object.entry("name").or_insert_with(|| "".into());
+        if let Some(Value::String(kind)) = object.get_mut("type") {
+            if let Some(suffix) = kind.strip_prefix("trcToken") {
+                *kind = format!("uint256{}", suffix);
+            }
+        }
+        if let Some(Value::Array(components)) = object.get_mut("components") {
+            components.iter_mut().for_each(normalize_param);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::smart_contract::Abi;
+    use crate::apis::{BytesMessage, TransactionExtention, TriggerSmartContract};
+    use crate::key::PrivateKey;
+    use crate::services::mock::MockWallet;
+    use crate::services::{Service, ServiceConfig};
+
+    const ERC20_ABI: &str = r#"[
+        {"type": "constructor", "inputs": [{"name": "supply", "type": "uint256"}], "stateMutability": "nonpayable"},
+        {"type": "function", "name": "balanceOf", "inputs": [{"name": "owner", "type": "address"}],
+         "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"},
+        {"type": "function", "name": "transfer", "inputs": [{"name": "to", "type": "address"}, {"name": "value", "type": "uint256"}],
+         "outputs": [{"name": "", "type": "bool"}], "stateMutability": "nonpayable"},
+        {"type": "function", "name": "transfer", "inputs": [{"name": "to", "type": "address[]"}, {"name": "value", "type": "uint256"}],
+         "outputs": [], "stateMutability": "nonpayable"},
+        {"type": "event", "name": "Transfer", "anonymous": false, "inputs": [
+            {"name": "from", "type": "address", "indexed": true},
+            {"name": "to", "type": "address", "indexed": true},
+            {"name": "value", "type": "uint256", "indexed": false}]}
+    ]"#;
+
+    fn usdt() -> Address {
+        "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".parse().unwrap()
+    }
+
+    #[test]
+    fn test_from_json() {
+        let contract = Contract::from_json(usdt(), ERC20_ABI).unwrap();
+        assert!(contract.abi().constructor().is_some());
+        assert!(contract.abi().event("Transfer").is_ok());
+        assert_eq!(contract.function("transfer(address[],uint256)").unwrap().outputs.len(), 0);
+        assert!(contract.function("transfer(uint256)").is_err());
+        assert!(contract.function("approve").is_err());
+
+        // 重载的函数需要用签名调用
+        assert!(matches!(contract.function("transfer"), Err(Error::OverloadedFunction(ref signatures))
+            if signatures.contains("transfer(address,uint256)") && signatures.contains("transfer(address[],uint256)")));
+
+        // 地址字符串按 TRON 地址解析
+        let to = Address::from_evm([0x11; 20]);
+        let transfer = "transfer(address,uint256)";
+        let data = contract.encode_input(transfer, &[Token::String(to.to_string()), Token::Uint(1.into())]).unwrap();
+        assert_eq!(data, contract.encode_input(transfer, &[to.into(), Token::Uint(1.into())]).unwrap());
+        assert_eq!(hex::encode(&data[..4]), "a9059cbb");
+
+        let tokens = [Token::Array(vec![Token::String(to.to_string())]), Token::Uint(1.into())];
+        assert!(contract.encode_input("transfer(address[],uint256)", &tokens).is_ok());
+        assert!(contract.encode_input(transfer, &[Token::String("T123".into()), Token::Uint(1.into())]).is_err());
+        assert!(contract.encode_input(transfer, &[to.into()]).is_err());
+        assert!(contract.encode_input(transfer, &[to.into(), Token::Uint(1.into()), Token::Uint(2.into())]).is_err());
+    }
+
+    #[test]
+    fn test_decode_output_addresses() {
+        let json = r#"[{"type": "function", "name": "owners", "inputs": [],
+            "outputs": [{"name": "", "type": "address"}, {"name": "", "type": "address[]"}], "stateMutability": "view"}]"#;
+        let contract = Contract::from_json(usdt(), json).unwrap();
+
+        let owner = Address::from_evm([0x11; 20]);
+        let output = ethabi::encode(&[owner.into(), Token::Array(vec![usdt().into()])]);
+        let tokens = contract.decode_output("owners", &output).unwrap();
+        assert_eq!(tokens, vec![owner.into(), Token::Array(vec![usdt().into()])]);
+
+        // 地址可以转换回 TRON 地址，不会与 `string` 返回值混淆
+        assert_eq!(Address::try_from(tokens[0].clone()).unwrap(), owner);
+        assert!(tokens[0].clone().into_string().is_none());
+    }
+
+    #[test]
+    fn test_from_tron_json() {
+        let json = r#"{"entrys": [
+            {"constant": true, "name": "balanceOf", "inputs": [{"name": "who", "type": "address"}],
+             "outputs": [{"type": "uint256"}], "type": "Function", "stateMutability": "View"},
+            {"name": "deposit", "inputs": [{"name": "id", "type": "trcToken"}], "payable": true,
+             "type": "Function", "stateMutability": "Payable"},
+            {"type": "Fallback", "payable": true, "stateMutability": "Payable"}
+        ]}"#;
+
+        let contract = Contract::from_json(usdt(), json).unwrap();
+        assert_eq!(contract.function("balanceOf").unwrap().state_mutability, ethabi::StateMutability::View);
+        assert_eq!(contract.function("deposit").unwrap().inputs[0].kind, ParamType::Uint(256));
+        assert!(contract.abi().fallback);
+    }
+
+    fn smart_contract() -> SmartContract {
+        let param = |name: &str, r#type: &str| Param { name: name.into(), r#type: r#type.into(), indexed: false };
+        let entry = Entry {
+            name: "balanceOf".into(),
+            inputs: vec![param("who", "address")],
+            outputs: vec![param("", "uint256")],
+            r#type: EntryType::Function as i32,
+            state_mutability: StateMutabilityType::View as i32,
+            ..Default::default()
+        };
+
+        SmartContract {
+            contract_address: usdt().into(),
+            abi: Some(Abi { entrys: vec![entry] }),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_and_call() {
+        let endpoint = MockWallet::new()
+            .unary("GetContract", |request: BytesMessage| {
+                if request.value == Vec::from(usdt()) {
+                    Ok(smart_contract())
+                } else {
+                    Ok(SmartContract::default())
+                }
+            })
+            .unary("TriggerConstantContract", |request: TriggerSmartContract| {
+                assert_eq!(&request.data[16..36], &Address::from_evm([0x22; 20]).evm_address());
+                Ok(TransactionExtention {
+                    constant_result: vec![ethabi::encode(&[Token::Uint(42.into())])],
+                    ..Default::default()
+                })
+            })
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let agent = service.agent(PrivateKey::generate());
+
+        let contract = Contract::load(&agent, &usdt()).await.unwrap();
+        assert_eq!(contract.address(), &usdt());
+
+        let owner = Address::from_evm([0x22; 20]).to_string();
+        let output = contract.call(&agent, "balanceOf", &[Token::String(owner)]).await.unwrap();
+        assert_eq!(output, vec![Token::Uint(42.into())]);
+        assert!(contract.call(&agent, "balanceOf", &[]).await.is_err());
+
+        let missing = Address::try_from(&[0x41; 21][..]).unwrap();
+        assert!(matches!(Contract::load(&agent, &missing).await, Err(Error::ContractNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_call_solidity_revert() {
+        use crate::apis::{transaction, Return, Transaction};
+        use crate::apis::transaction::result::ContractResult;
+        use crate::error::RevertReason;
+
+        // 全节点与 solidity 节点是两个独立的服务，全节点上没有注册任何方法
+        let endpoint = MockWallet::new().serve().await;
+        let solidity_endpoint = MockWallet::new()
+            .unary("WalletSolidity/TriggerConstantContract", |_: TriggerSmartContract| {
+                let mut revert = RevertReason::ERROR_SELECTOR.to_vec();
+                revert.extend(ethabi::encode(&[Token::String("not owner".into())]));
+
+                Ok(TransactionExtention {
+                    transaction: Some(Transaction {
+                        ret: vec![transaction::Result { contract_ret: ContractResult::Revert as i32, ..Default::default() }],
+                        ..Default::default()
+                    }),
+                    result: Some(Return { result: true, ..Default::default() }),
+                    constant_result: vec![revert],
+                    ..Default::default()
+                })
+            })
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig {
+            solidity_endpoint: Some(solidity_endpoint),
+            ..ServiceConfig::new(endpoint)
+        }).await.unwrap();
+        let agent = service.agent(PrivateKey::generate());
+        let contract = Contract::from_smart_contract(&smart_contract()).unwrap();
+
+        let owner = [Token::String(usdt().to_string())];
+        let error = contract.call_solidity(&agent, "balanceOf", &owner).await.unwrap_err();
+        assert!(matches!(error, Error::Revert(RevertReason::Error(ref message)) if message == "not owner"));
+
+        // 全节点的 Wallet 服务没有注册，说明 solidity 调用没有发到全节点
+        assert!(matches!(contract.call(&agent, "balanceOf", &owner).await, Err(Error::Rpc(_))));
+    }
+}
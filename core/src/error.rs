@@ -1,7 +1,393 @@
+use std::fmt;
+
 use thiserror::Error;
 
+use crate::apis::r#return::ResponseCode;
+use crate::apis::transaction::result::ContractResult;
+use crate::apis::{Return, TransactionExtention};
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Transaction list is empty.")]
     EmptyTransaction,
-}
\ No newline at end of file
+    /// 服务配置无效
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+    /// 交易选项不适用于该交易，如 TRX 转账设置了 `call_value`
+    #[error("Invalid transaction options: {0}")]
+    InvalidOptions(String),
+    /// 连接节点超时
+    #[error("Connect to {0} timed out.")]
+    ConnectTimeout(String),
+    /// 建立连接时的传输层错误
+    #[error("Transport error: {0}")]
+    Transport(#[from] tonic::transport::Error),
+    /// gRPC 调用失败
+    #[error("RPC failed: {0}")]
+    Rpc(#[from] tonic::Status),
+    /// 节点创建交易失败（`TransactionExtention.result`）
+    #[error("Create transaction failed, {error}")]
+    Transaction {
+        error: NodeError,
+        /// `trigger_contract` 的执行结果，合约 revert 时包含 revert 数据
+        constant_result: Vec<Vec<u8>>,
+        /// `trigger_contract` 预执行消耗的能量
+        energy_used: i64,
+    },
+    /// 节点拒绝广播交易（`broadcast_transaction` 返回的 `Return`）
+    #[error("Broadcast transaction failed, {0}")]
+    Broadcast(NodeError),
+    /// 广播失败后无法确认交易是否已经上链，为避免重复发送不再重试
+    #[error("Broadcast outcome of transaction {txid} is unknown, {source}")]
+    BroadcastUnknown { txid: String, source: tonic::Status },
+    /// 引用区块的哈希长度不足
+    #[error("Invalid block reference, block hash: {0}")]
+    InvalidBlockReference(String),
+    /// 本地计算的交易 ID 与节点返回的不一致
+    #[error("Transaction id mismatch, node returned {expected}, computed {actual}.")]
+    TxidMismatch { expected: String, actual: String },
+    /// 交易签名或使用的账户权限无效（`get_transaction_sign_weight` 返回的错误）
+    #[error("Check permission failed, {0}")]
+    Permission(String),
+    /// 多签交易的签名权重未达到权限阈值
+    #[error("Signature weight {current} is less than threshold {threshold}.")]
+    InsufficientWeight { current: i64, threshold: i64 },
+    /// 地址格式、前缀或校验和无效
+    #[error("Invalid address: {0}")]
+    Address(#[from] AddressError),
+    /// 助记词无效
+    #[error("Invalid mnemonic: {0}")]
+    Mnemonic(String),
+    /// BIP-32 派生路径或扩展密钥无效
+    #[error("HD wallet error: {0}")]
+    HdWallet(#[from] bip32::Error),
+    /// keystore 文件格式错误或参数不支持
+    #[error("Keystore error: {0}")]
+    Keystore(String),
+    /// keystore 的 MAC 校验失败
+    #[error("Invalid keystore password.")]
+    InvalidPassword,
+    /// TIP-712 结构化数据无效
+    #[error("Invalid typed data: {0}")]
+    TypedData(String),
+    /// ABI 编码或解码失败
+    #[error("ABI error: {0}")]
+    Abi(#[from] ethabi::Error),
+    /// 合约执行回滚，包含解码后的回滚原因
+    #[error("Contract reverted: {0}")]
+    Revert(RevertReason),
+    /// 合约执行失败（回滚以外的原因，如能量不足）
+    #[error("Contract execution failed: {0:?}")]
+    ContractExecution(ContractResult),
+    /// 节点返回的链参数中缺少估算费用需要的参数
+    #[error("Chain parameter {0} not found.")]
+    ChainParameter(String),
+    /// `get_contract` 没有找到该地址的合约
+    #[error("Contract {0} not found.")]
+    ContractNotFound(String),
+    /// 函数有多个重载，需要用完整的签名调用，值为可用的签名
+    #[error("Function is overloaded, call it by one of the signatures: {0}")]
+    OverloadedFunction(String),
+    /// 代币数量的格式错误、精度超出小数位数或溢出
+    #[error("Invalid token amount: {0}")]
+    InvalidAmount(String),
+    /// 签名失败
+    #[error("Sign failed: {0}")]
+    Signing(#[from] k256::ecdsa::Error),
+    /// 读写文件失败
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl Error {
+    /// 节点返回的错误码，仅 [`Error::Transaction`] 和 [`Error::Broadcast`] 有值
+    pub fn response_code(&self) -> Option<ResponseCode> {
+        match self {
+            Error::Transaction { error, .. } | Error::Broadcast(error) => Some(error.code),
+            _ => None,
+        }
+    }
+
+    /// 检查节点创建交易的结果，失败时返回 [`Error::Transaction`]
+    pub fn check_transaction(transaction_ext: &TransactionExtention) -> Result<(), Error> {
+        match &transaction_ext.result {
+            Some(ret) => NodeError::check(ret).map_err(|error| Error::Transaction {
+                error,
+                constant_result: transaction_ext.constant_result.clone(),
+                energy_used: transaction_ext.energy_used,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// 检查只读调用的结果，返回合约的返回数据
+    ///
+    /// 合约回滚时返回 [`Error::Revert`]：新版节点在 `transaction.ret` 中给出 `REVERT`，
+    /// 旧版节点的 `result` 为失败，两者的 `constant_result` 都是回滚数据。
+    pub fn check_constant_result(transaction_ext: &TransactionExtention) -> Result<Vec<u8>, Error> {
+        let data = transaction_ext.constant_result.first().cloned().unwrap_or_default();
+
+        if let Err(error) = Error::check_transaction(transaction_ext) {
+            return Err(match RevertReason::decode(&data) {
+                RevertReason::Raw(_) => error,
+                reason => Error::Revert(reason),
+            });
+        }
+
+        let contract_ret = transaction_ext.transaction.as_ref()
+            .and_then(|transaction| transaction.ret.first())
+            .and_then(|ret| ContractResult::from_i32(ret.contract_ret))
+            .unwrap_or(ContractResult::Default);
+
+        match contract_ret {
+            ContractResult::Default | ContractResult::Success => Ok(data),
+            ContractResult::Revert => Err(Error::Revert(RevertReason::decode(&data))),
+            contract_ret => Err(Error::ContractExecution(contract_ret)),
+        }
+    }
+}
+
+/// 合约回滚的原因，由回滚数据解码
+#[derive(Debug, Clone, PartialEq)]
+pub enum RevertReason {
+    /// `Error(string)`，`require` 和 `revert` 给出的消息
+    Error(String),
+    /// `Panic(uint256)`，如断言失败、算术溢出和数组越界
+    Panic(ethabi::Uint),
+    /// 自定义错误或没有给出原因
+    Raw(Vec<u8>),
+}
+
+impl RevertReason {
+    /// `Error(string)` 的函数选择器
+    pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+    /// `Panic(uint256)` 的函数选择器
+    pub const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+    pub fn decode(data: &[u8]) -> Self {
+        use ethabi::ParamType;
+
+        let decoded = match data.get(..4) {
+            Some(selector) if selector == Self::ERROR_SELECTOR => ethabi::decode(&[ParamType::String], &data[4..])
+                .ok()
+                .and_then(|mut tokens| tokens.pop()?.into_string())
+                .map(RevertReason::Error),
+            Some(selector) if selector == Self::PANIC_SELECTOR => ethabi::decode(&[ParamType::Uint(256)], &data[4..])
+                .ok()
+                .and_then(|mut tokens| tokens.pop()?.into_uint())
+                .map(RevertReason::Panic),
+            _ => None,
+        };
+
+        decoded.unwrap_or_else(|| RevertReason::Raw(data.to_vec()))
+    }
+
+    /// `Panic` 错误码的含义
+    fn panic_description(code: &ethabi::Uint) -> &'static str {
+        if *code > ethabi::Uint::from(0xff) {
+            return "unknown panic";
+        }
+
+        match code.low_u32() {
+            0x00 => "generic panic",
+            0x01 => "assertion failed",
+            0x11 => "arithmetic overflow or underflow",
+            0x12 => "division or modulo by zero",
+            0x21 => "invalid enum value",
+            0x22 => "invalid storage byte array",
+            0x31 => "pop on empty array",
+            0x32 => "array index out of bounds",
+            0x41 => "out of memory",
+            0x51 => "call to zero-initialized function",
+            _ => "unknown panic",
+        }
+    }
+}
+
+impl fmt::Display for RevertReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevertReason::Error(message) => f.write_str(message),
+            RevertReason::Panic(code) => write!(f, "{} (panic code 0x{:02x})", Self::panic_description(code), code),
+            RevertReason::Raw(data) if data.is_empty() => f.write_str("no reason given"),
+            RevertReason::Raw(data) => write!(f, "0x{}", hex::encode(data)),
+        }
+    }
+}
+
+/// 地址解析错误
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum AddressError {
+    #[error("invalid length {0}")]
+    InvalidLength(usize),
+    #[error("invalid checksum")]
+    InvalidChecksum,
+    #[error("invalid prefix 0x{0:02x}, expected 0x41")]
+    InvalidPrefix(u8),
+    #[error("invalid base58: {0}")]
+    Base58(#[from] bs58::decode::Error),
+    #[error("invalid hex: {0}")]
+    Hex(#[from] hex::FromHexError),
+}
+
+/// 节点在 [`Return`] 中返回的错误
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeError {
+    pub code: ResponseCode,
+    /// UTF-8 解码后的 `Return.message`
+    pub message: String,
+}
+
+impl NodeError {
+    /// `Return.result` 为 `false` 时返回对应的错误
+    pub fn check(ret: &Return) -> Result<(), NodeError> {
+        if ret.result {
+            Ok(())
+        } else {
+            Err(NodeError::from(ret))
+        }
+    }
+}
+
+impl From<&Return> for NodeError {
+    fn from(ret: &Return) -> Self {
+        NodeError {
+            // 未知的错误码按 OTHER_ERROR 处理
+            code: ResponseCode::from_i32(ret.code).unwrap_or(ResponseCode::OtherError),
+            message: String::from_utf8_lossy(&ret.message).into_owned(),
+        }
+    }
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = match self.code {
+            ResponseCode::Success => "SUCCESS",
+            ResponseCode::Sigerror => "SIGERROR",
+            ResponseCode::ContractValidateError => "CONTRACT_VALIDATE_ERROR",
+            ResponseCode::ContractExeError => "CONTRACT_EXE_ERROR",
+            ResponseCode::BandwithError => "BANDWITH_ERROR",
+            ResponseCode::DupTransactionError => "DUP_TRANSACTION_ERROR",
+            ResponseCode::TaposError => "TAPOS_ERROR",
+            ResponseCode::TooBigTransactionError => "TOO_BIG_TRANSACTION_ERROR",
+            ResponseCode::TransactionExpirationError => "TRANSACTION_EXPIRATION_ERROR",
+            ResponseCode::ServerBusy => "SERVER_BUSY",
+            ResponseCode::NoConnection => "NO_CONNECTION",
+            ResponseCode::NotEnoughEffectiveConnection => "NOT_ENOUGH_EFFECTIVE_CONNECTION",
+            ResponseCode::OtherError => "OTHER_ERROR",
+        };
+
+        write!(f, "{}: {}", code, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_error() {
+        let ret = Return {
+            result: false,
+            code: ResponseCode::ContractValidateError as i32,
+            message: b"Validate TransferContract error, balance is not sufficient.".to_vec(),
+        };
+
+        let error = Error::Broadcast(NodeError::check(&ret).unwrap_err());
+        assert_eq!(error.response_code(), Some(ResponseCode::ContractValidateError));
+        assert_eq!(
+            error.to_string(),
+            "Broadcast transaction failed, CONTRACT_VALIDATE_ERROR: Validate TransferContract error, balance is not sufficient."
+        );
+
+        let ret = Return { result: false, code: 99, message: vec![0xff] };
+        assert_eq!(NodeError::from(&ret), NodeError { code: ResponseCode::OtherError, message: "\u{fffd}".into() });
+
+        assert!(NodeError::check(&Return { result: true, ..Default::default() }).is_ok());
+    }
+
+    #[test]
+    fn test_revert_reason() {
+        let mut data = RevertReason::ERROR_SELECTOR.to_vec();
+        data.extend(ethabi::encode(&[ethabi::Token::String("balance not enough".into())]));
+        assert_eq!(RevertReason::decode(&data), RevertReason::Error("balance not enough".into()));
+
+        let mut data = RevertReason::PANIC_SELECTOR.to_vec();
+        data.extend(ethabi::encode(&[ethabi::Token::Uint(0x11.into())]));
+        let reason = RevertReason::decode(&data);
+        assert_eq!(reason, RevertReason::Panic(0x11.into()));
+        assert_eq!(reason.to_string(), "arithmetic overflow or underflow (panic code 0x11)");
+
+        // 数据不完整时保留原始数据
+        assert_eq!(RevertReason::decode(&data[..10]), RevertReason::Raw(data[..10].to_vec()));
+        assert_eq!(RevertReason::decode(&[]).to_string(), "no reason given");
+    }
+
+    #[test]
+    fn test_check_constant_result() {
+        use crate::apis::{transaction, Transaction};
+
+        let mut revert = RevertReason::ERROR_SELECTOR.to_vec();
+        revert.extend(ethabi::encode(&[ethabi::Token::String("paused".into())]));
+        let with_ret = |contract_ret: ContractResult| TransactionExtention {
+            transaction: Some(Transaction {
+                ret: vec![transaction::Result { contract_ret: contract_ret as i32, ..Default::default() }],
+                ..Default::default()
+            }),
+            result: Some(Return { result: true, ..Default::default() }),
+            constant_result: vec![revert.clone()],
+            ..Default::default()
+        };
+
+        assert_eq!(Error::check_constant_result(&with_ret(ContractResult::Success)).unwrap(), revert);
+        assert!(matches!(
+            Error::check_constant_result(&with_ret(ContractResult::Revert)),
+            Err(Error::Revert(RevertReason::Error(message))) if message == "paused"
+        ));
+        assert!(matches!(
+            Error::check_constant_result(&with_ret(ContractResult::OutOfEnergy)),
+            Err(Error::ContractExecution(ContractResult::OutOfEnergy))
+        ));
+
+        // 旧版节点以失败的 result 表示回滚
+        let transaction_ext = TransactionExtention {
+            result: Some(Return {
+                result: false,
+                code: ResponseCode::ContractExeError as i32,
+                message: b"REVERT opcode executed".to_vec(),
+            }),
+            constant_result: vec![revert.clone()],
+            ..Default::default()
+        };
+        assert_eq!(Error::check_constant_result(&transaction_ext).unwrap_err().to_string(), "Contract reverted: paused");
+
+        let transaction_ext = TransactionExtention { constant_result: vec![vec![1, 2]], ..transaction_ext };
+        assert!(matches!(Error::check_constant_result(&transaction_ext), Err(Error::Transaction { .. })));
+    }
+
+    #[test]
+    fn test_check_transaction() {
+        let transaction_ext = TransactionExtention {
+            result: Some(Return {
+                result: false,
+                code: ResponseCode::ContractValidateError as i32,
+                message: b"account does not exist".to_vec(),
+            }),
+            constant_result: vec![vec![0x08, 0xc3, 0x79, 0xa0]],
+            energy_used: 345,
+            ..Default::default()
+        };
+
+        match Error::check_transaction(&transaction_ext) {
+            Err(Error::Transaction { error, constant_result, energy_used }) => {
+                assert_eq!(error.code, ResponseCode::ContractValidateError);
+                assert_eq!(error.message, "account does not exist");
+                assert_eq!(constant_result, transaction_ext.constant_result);
+                assert_eq!(energy_used, 345);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        assert!(Error::check_transaction(&TransactionExtention::default()).is_ok());
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bip32::{ChildNumber, DerivationPath, Prefix, XPrv, XPub};
+use bip39::{Mnemonic, MnemonicType, Seed};
+
+pub use bip39::Language;
+
+use crate::error::Error;
+use crate::key::{Address, PrivateKey, PublicKey};
+use crate::Result;
+
+/// TRON 在 SLIP-44 中的币种编号
+pub const TRON_COIN_TYPE: u32 = 195;
+
+/// 生成指定词数（12、15、18、21 或 24）的助记词
+pub fn generate_mnemonic(words: usize, language: Language) -> Result<String> {
+    let mnemonic_type = MnemonicType::for_word_count(words).map_err(|e| Error::Mnemonic(e.to_string()))?;
+
+    Ok(Mnemonic::new(mnemonic_type, language).into_phrase())
+}
+
+/// 检查助记词的单词和校验和
+pub fn validate_mnemonic(phrase: &str, language: Language) -> Result<()> {
+    Mnemonic::validate(phrase, language).map_err(|e| Error::Mnemonic(e.to_string()))
+}
+
+/// BIP-44 账户路径 `m/44'/195'/account'`
+pub fn account_path(account: u32) -> Result<DerivationPath> {
+    Ok(format!("m/44'/{}'/{}'", TRON_COIN_TYPE, account).parse()?)
+}
+
+/// HD 钱包，按 BIP-32 / BIP-44 派生私钥
+pub struct HdWallet {
+    root: XPrv,
+}
+
+impl HdWallet {
+    /// 由 BIP-39 助记词和密码（可以为空）创建
+    pub fn from_mnemonic(phrase: &str, passphrase: &str, language: Language) -> Result<Self> {
+        let mnemonic = Mnemonic::from_phrase(phrase, language).map_err(|e| Error::Mnemonic(e.to_string()))?;
+
+        Self::from_seed(Seed::new(&mnemonic, passphrase).as_bytes())
+    }
+
+    pub fn from_seed(seed: &[u8]) -> Result<Self> {
+        Ok(Self { root: XPrv::new(seed)? })
+    }
+
+    /// 按路径派生私钥，如 `m/44'/195'/0'/0/0`
+    pub fn derive(&self, path: &str) -> Result<PrivateKey> {
+        let key = self.derive_xprv(&path.parse()?)?;
+
+        Ok(PrivateKey::new(key.private_key().clone()))
+    }
+
+    /// 派生 `m/44'/195'/account'/0/index` 的私钥
+    pub fn derive_key(&self, account: u32, index: u32) -> Result<PrivateKey> {
+        self.derive(&format!("m/44'/{}'/{}'/0/{}", TRON_COIN_TYPE, account, index))
+    }
+
+    /// 账户 `m/44'/195'/account'` 的扩展公钥，交给只派生地址、不持有私钥的服务使用
+    pub fn account_xpub(&self, account: u32) -> Result<ExtendedPublicKey> {
+        Ok(ExtendedPublicKey(self.derive_xprv(&account_path(account)?)?.public_key()))
+    }
+
+    fn derive_xprv(&self, path: &DerivationPath) -> Result<XPrv> {
+        path.iter().try_fold(self.root.clone(), |key, child| Ok(key.derive_child(child)?))
+    }
+}
+
+/// 扩展公钥，只能派生非硬化路径上的公钥和地址
+#[derive(Clone)]
+pub struct ExtendedPublicKey(XPub);
+
+impl ExtendedPublicKey {
+    /// 派生 `0/index` 的公钥，用于账户扩展公钥
+    pub fn derive_public_key(&self, index: u32) -> Result<PublicKey> {
+        let key = self.0
+            .derive_child(ChildNumber::new(0, false)?)?
+            .derive_child(ChildNumber::new(index, false)?)?;
+
+        Ok(PublicKey::new(*key.public_key()))
+    }
+
+    /// 派生 `0/index` 的地址，与 [`HdWallet::derive_key`] 的地址相同
+    pub fn derive_address(&self, index: u32) -> Result<Address> {
+        Ok(*self.derive_public_key(index)?.address())
+    }
+}
+
+impl FromStr for ExtendedPublicKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(ExtendedPublicKey(s.parse()?))
+    }
+}
+
+/// 以 `xpub` 开头的 base58 编码
+impl fmt::Display for ExtendedPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.to_string(Prefix::XPUB))
+    }
+}
+
+impl fmt::Debug for ExtendedPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ExtendedPublicKey").field(&self.to_string()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_derive_key() {
+        let wallet = HdWallet::from_mnemonic(PHRASE, "", Language::English).unwrap();
+
+        let key = wallet.derive_key(0, 0).unwrap();
+        assert_eq!(key.address_string(), "TUEZSdKsoDHQMeZwihtdoBiN46zxhGWYdH");
+        assert_eq!(wallet.derive("m/44'/195'/0'/0/0").unwrap(), key);
+        assert_ne!(wallet.derive_key(0, 1).unwrap(), key);
+
+        // 密码不同时派生出不同的钱包
+        let wallet = HdWallet::from_mnemonic(PHRASE, "TREZOR", Language::English).unwrap();
+        assert_ne!(wallet.derive_key(0, 0).unwrap(), key);
+
+        assert!(HdWallet::from_mnemonic("abandon abandon abandon", "", Language::English).is_err());
+        assert!(wallet.derive("m/44'/x").is_err());
+    }
+
+    #[test]
+    fn test_xpub_derive_address() {
+        let wallet = HdWallet::from_mnemonic(PHRASE, "", Language::English).unwrap();
+        let xpub = wallet.account_xpub(0).unwrap().to_string();
+        assert!(xpub.starts_with("xpub"));
+
+        let xpub: ExtendedPublicKey = xpub.parse().unwrap();
+        for index in 0..3 {
+            assert_eq!(xpub.derive_address(index).unwrap(), *wallet.derive_key(0, index).unwrap().address());
+        }
+    }
+
+    #[test]
+    fn test_mnemonic() {
+        for language in [Language::English, Language::ChineseSimplified, Language::Japanese] {
+            let phrase = generate_mnemonic(24, language).unwrap();
+            assert!(validate_mnemonic(&phrase, language).is_ok());
+            assert!(HdWallet::from_mnemonic(&phrase, "", language).is_ok());
+        }
+
+        assert!(generate_mnemonic(13, Language::English).is_err());
+        assert!(validate_mnemonic(&PHRASE.replace("about", "abandon"), Language::English).is_err());
+    }
+}
@@ -1,39 +1,42 @@
-use k256::ecdsa::{SigningKey, Error};
+use k256::ecdsa::{SigningKey, VerifyingKey, Error};
+use k256::FieldBytes;
 use rand::rngs::OsRng;
+use std::convert::TryFrom;
+use std::fmt;
 use std::ops::Deref;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use k256::elliptic_curve::sec1::ToEncodedPoint;
 use sha3::{Keccak256, Digest};
 use crate::utils::raw_address_base_check;
 use k256::ecdsa::recoverable;
-use k256::ecdsa::signature::DigestSigner;
+use k256::ecdsa::signature::{DigestSigner, DigestVerifier};
+use crate::signer::Prehashed;
+use crate::error::AddressError;
+
+pub use typed_data::{TypedData, TypedField};
+
+mod typed_data;
 
 pub const PAD: u8 = 0x41;
 
-#[derive(Debug, Eq, PartialEq)]
+/// 私钥
+///
+/// `Debug` 不输出私钥，释放时 `SigningKey` 会清零私钥，只能通过 [`PrivateKey::to_secret_bytes`] 取出私钥。
+#[derive(Eq, PartialEq)]
 pub struct PrivateKey {
     key: SigningKey,
-    address: Address,
+    public_key: PublicKey,
 }
 
 impl PrivateKey {
     pub fn new(key: SigningKey) -> Self {
-        let verify_key = key.verifying_key()
-            .to_encoded_point(true)
-            .to_untagged_bytes()
-            .unwrap();
-
-        let mut buf = [0; 21];
-        let hash = Keccak256::digest(&verify_key);
-
-        // 取H的最后20字节，在前面填充一个字节0x41得到address
-        buf[0] = PAD; // 填充第一个字节为 0x41
-        &buf[1..].copy_from_slice(&hash[12..32]);
-
-        let address = Address::from(buf);
+        let public_key = PublicKey::new(key.verifying_key());
 
         PrivateKey {
             key,
-            address
+            public_key
         }
     }
 
@@ -42,50 +45,215 @@ impl PrivateKey {
     }
 
     pub fn generate() -> Self {
-        PrivateKey::new(SigningKey::random(OsRng::default()))
+        PrivateKey::new(SigningKey::random(OsRng))
     }
 
     pub fn address(&self) -> &Address {
-        &self.address
+        self.public_key.address()
     }
 
-    pub fn key(&self) -> &SigningKey {
+    pub(crate) fn key(&self) -> &SigningKey {
         &self.key
     }
 
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
     pub fn address_string(&self) -> String {
-        self.address.to_base58()
+        self.public_key.address().to_base58()
+    }
+
+    /// 十六进制的私钥，离开作用域时清零
+    pub fn private_key_string(&self) -> SecretString {
+        let bytes = self.to_secret_bytes();
+        let mut buf = Zeroizing::new([0; 64]);
+        hex::encode_to_slice(&bytes[..], &mut buf[..]).expect("buffer size matches");
+
+        SecretString::new(String::from_utf8(buf.to_vec()).expect("hex is valid utf-8"))
     }
 
-    pub fn private_key_string(&self) -> String {
-        hex::encode(self.to_bytes())
+    /// 私钥的字节，离开作用域时清零
+    pub fn to_secret_bytes(&self) -> Zeroizing<[u8; 32]> {
+        let mut bytes = Zeroizing::new([0; 32]);
+        bytes.copy_from_slice(&self.key.to_bytes());
+        bytes
     }
 
     /// 对数据进行签名
     ///
     /// 常用于交易数据的签名，该签名符合以太坊风格的“可恢复签名”。
     pub fn sign<T: AsRef<[u8]>>(&self, data: &T) -> recoverable::Signature {
+        self.try_sign(data).expect("signature operation failed")
+    }
+
+    /// 与 [`PrivateKey::sign`] 相同，签名失败时返回错误而不是 panic
+    pub fn try_sign<T: AsRef<[u8]>>(&self, data: &T) -> Result<recoverable::Signature, Error> {
         // Signature
         let mut hasher = sha2::Sha256::new();
         hasher.update(data);
 
-        self.sign_digest(hasher)
+        self.key.try_sign_digest(hasher)
+    }
+
+    /// 按 TIP-191 签名消息，与 TronWeb 的 `signMessageV2` 相同
+    ///
+    /// 返回 65 字节的 `r || s || v`，`v` 为 27 或 28。
+    pub fn sign_message<T: AsRef<[u8]>>(&self, message: &T) -> Result<Vec<u8>, Error> {
+        self.sign_prehashed(&message_hash(message))
+    }
+
+    /// 按 TIP-712 签名结构化数据，与 TronWeb 的 `_signTypedData` 相同，签名格式同 [`PrivateKey::sign_message`]
+    pub fn sign_typed_data(&self, typed_data: &TypedData) -> crate::Result<Vec<u8>> {
+        Ok(self.sign_prehashed(&typed_data.signing_hash()?)?)
+    }
+
+    /// 对摘要签名，`v` 加上 27
+    fn sign_prehashed(&self, hash: &[u8; 32]) -> Result<Vec<u8>, Error> {
+        let signature: recoverable::Signature = self.key.try_sign_digest(Prehashed::new(hash))?;
+
+        let mut signature = signature.as_ref().to_vec();
+        signature[64] += 27;
+        Ok(signature)
     }
 }
 
-impl Deref for PrivateKey {
-    type Target = SigningKey;
+/// TIP-191 消息摘要：`keccak256("\x19TRON Signed Message:\n" + len(message) + message)`
+pub fn message_hash<T: AsRef<[u8]>>(message: &T) -> [u8; 32] {
+    let message = message.as_ref();
+    let mut hasher = Keccak256::new();
+    hasher.update(format!("\x19TRON Signed Message:\n{}", message.len()));
+    hasher.update(message);
+    hasher.finalize().into()
+}
+
+/// 从 TIP-191 签名中恢复签名者的地址，与 TronWeb 的 `verifyMessageV2` 相同
+pub fn recover_message<T: AsRef<[u8]>>(message: &T, signature: &[u8]) -> Result<Address, Error> {
+    Ok(*PublicKey::recover(&message_hash(message), signature)?.address())
+}
 
-    fn deref(&self) -> &Self::Target {
+/// 检查 TIP-191 签名是否由 `address` 签名
+pub fn verify_message<T: AsRef<[u8]>>(address: &Address, message: &T, signature: &[u8]) -> bool {
+    recover_message(message, signature).ok().as_ref() == Some(address)
+}
+
+/// 从 TIP-712 签名中恢复签名者的地址
+pub fn recover_typed_data(typed_data: &TypedData, signature: &[u8]) -> crate::Result<Address> {
+    Ok(*PublicKey::recover(&typed_data.signing_hash()?, signature)?.address())
+}
+
+/// 检查 TIP-712 签名是否由 `address` 签名
+pub fn verify_typed_data(address: &Address, typed_data: &TypedData, signature: &[u8]) -> bool {
+    recover_typed_data(typed_data, signature).ok().as_ref() == Some(address)
+}
+
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivateKey")
+            .field("address", &self.address().to_base58())
+            .field("key", &"<redacted>")
+            .finish()
+    }
+}
+
+/// 离开作用域时清零的字符串，`Debug` 不输出内容
+#[derive(Clone, Eq, PartialEq)]
+pub struct SecretString(Zeroizing<String>);
+
+impl SecretString {
+    pub fn new(secret: String) -> Self {
+        SecretString(Zeroizing::new(secret))
+    }
+
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        SecretString::new(secret)
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(<redacted>)")
+    }
+}
+
+/// 公钥
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PublicKey {
+    key: VerifyingKey,
+    address: Address,
+}
+
+impl PublicKey {
+    pub fn new(key: VerifyingKey) -> Self {
+        let verify_key = key
+            .to_encoded_point(false)
+            .to_untagged_bytes()
+            .unwrap();
+
+        let mut buf = [0; 21];
+        let hash = Keccak256::digest(&verify_key);
+
+        // 取H的最后20字节，在前面填充一个字节0x41得到address
+        buf[0] = PAD; // 填充第一个字节为 0x41
+        buf[1..].copy_from_slice(&hash[12..32]);
+
+        PublicKey {
+            key,
+            address: Address::from(buf),
+        }
+    }
+
+    /// 从 SEC1 编码（压缩或未压缩）的公钥创建
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(PublicKey::new(VerifyingKey::from_sec1_bytes(bytes)?))
+    }
+
+    /// 从 SHA-256 摘要和 65 字节的可恢复签名中恢复公钥，并验证签名
+    ///
+    /// 签名的最后一个字节可以是 0/1，也可以是以太坊风格的 27/28。
+    pub fn recover(hash: &[u8; 32], signature: &[u8]) -> Result<Self, Error> {
+        if signature.len() != 65 {
+            return Err(Error::new());
+        }
+
+        let mut bytes = [0; 65];
+        bytes.copy_from_slice(signature);
+        if bytes[64] >= 27 {
+            bytes[64] -= 27;
+        }
+
+        let signature = recoverable::Signature::try_from(&bytes[..])?;
+        let key = signature.recover_verify_key_from_digest_bytes(&FieldBytes::from(*hash))?;
+        key.verify_digest(Prehashed::new(hash), &k256::ecdsa::Signature::from(signature))?;
+
+        Ok(PublicKey::new(key))
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn key(&self) -> &VerifyingKey {
         &self.key
     }
+
+    /// 33 字节的压缩公钥
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.key.to_bytes().to_vec()
+    }
 }
 
 pub type RawAddress = [u8; 21];
 pub type BaseCheck = [u8; 4];
 pub type CheckedAddress = [u8; 25];
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct Address {
     raw_address: RawAddress,
     base_check: BaseCheck,
@@ -99,15 +267,15 @@ impl Deref for Address {
     }
 }
 
-impl Into<Vec<u8>> for Address {
-    fn into(self) -> Vec<u8> {
-        self.raw_address.to_vec()
+impl From<Address> for Vec<u8> {
+    fn from(address: Address) -> Self {
+        address.raw_address.to_vec()
     }
 }
 
-impl Into<Vec<u8>> for &Address {
-    fn into(self) -> Vec<u8> {
-        self.raw_address.to_vec()
+impl From<&Address> for Vec<u8> {
+    fn from(address: &Address) -> Self {
+        address.raw_address.to_vec()
     }
 }
 
@@ -121,72 +289,349 @@ impl From<[u8; 21]> for Address {
     }
 }
 
-impl From<[u8; 25]> for Address {
-    fn from(checked_address: [u8; 25]) -> Self {
-        let mut raw_address = [0; 21];
-        let mut base_check = [0; 4];
-        raw_address.copy_from_slice(&checked_address[0..21]);
-        base_check.copy_from_slice(&checked_address[21..]);
+impl TryFrom<&[u8]> for Address {
+    type Error = AddressError;
 
-        Address {
-            raw_address,
-            base_check
+    /// 从 21 字节的地址创建，检查长度和 0x41 前缀
+    fn try_from(raw_address: &[u8]) -> Result<Self, Self::Error> {
+        let raw_address = RawAddress::try_from(raw_address)
+            .map_err(|_| AddressError::InvalidLength(raw_address.len()))?;
+        if raw_address[0] != PAD {
+            return Err(AddressError::InvalidPrefix(raw_address[0]));
         }
+
+        Ok(Address::from(raw_address))
+    }
+}
+
+impl TryFrom<CheckedAddress> for Address {
+    type Error = AddressError;
+
+    /// 从带校验和的 25 字节地址创建，检查 0x41 前缀和校验和
+    fn try_from(checked_address: CheckedAddress) -> Result<Self, Self::Error> {
+        let address = Address::try_from(&checked_address[..21])?;
+        if address.base_check[..] != checked_address[21..] {
+            return Err(AddressError::InvalidChecksum);
+        }
+
+        Ok(address)
     }
 }
 
 impl Address {
-    pub fn from_base58(s: &str) -> Result<Self, bs58::decode::Error> {
+    /// 解析 base58check 编码的地址，检查长度、前缀和校验和
+    pub fn from_base58(s: &str) -> Result<Self, AddressError> {
         let buf = bs58::decode(s).into_vec()?;
-        let mut checked_address = [0; 25];
-        checked_address.copy_from_slice(&buf);
+        let checked_address = CheckedAddress::try_from(&buf[..]).map_err(|_| AddressError::InvalidLength(buf.len()))?;
 
-        Ok(Address::from(checked_address))
+        Address::try_from(checked_address)
     }
 
-    pub fn from_hex(s: &str) -> Result<Self, hex::FromHexError> {
-        let mut raw_address = [0; 21];
-        hex::decode_to_slice(s, &mut raw_address)?;
-
-        Ok(Address::from(raw_address))
+    /// 解析十六进制的地址，可以是 `41` 开头的 21 字节地址，也可以是 20 字节的 EVM 地址，都可以带 `0x` 前缀
+    pub fn from_hex(s: &str) -> Result<Self, AddressError> {
+        let buf = hex::decode(s.strip_prefix("0x").unwrap_or(s))?;
+
+        match buf.len() {
+            20 => {
+                let mut evm_address = [0; 20];
+                evm_address.copy_from_slice(&buf);
+                Ok(Address::from_evm(evm_address))
+            }
+            _ => Address::try_from(&buf[..]),
+        }
     }
 
     pub fn to_hex(&self) -> String {
-        hex::encode(&self.raw_address)
+        hex::encode(self.raw_address)
     }
 
     pub fn to_base58(&self) -> String {
         let mut buf = [0; 25];
-        &buf[0..21].copy_from_slice(&self.raw_address);
-        &buf[21..].copy_from_slice(&self.base_check);
+        buf[0..21].copy_from_slice(&self.raw_address);
+        buf[21..].copy_from_slice(&self.base_check);
         bs58::encode(&buf).into_string()
     }
 
     pub fn raw_address(&self) -> &[u8] {
         &self.raw_address
     }
+
+    /// 从 20 字节的 EVM 地址创建
+    pub fn from_evm(evm_address: [u8; 20]) -> Self {
+        let mut raw_address = [PAD; 21];
+        raw_address[1..].copy_from_slice(&evm_address);
+
+        Address::from(raw_address)
+    }
+
+    /// 去掉 0x41 前缀的 20 字节 EVM 地址，用于 ABI 编码
+    pub fn evm_address(&self) -> [u8; 20] {
+        let mut buf = [0; 20];
+        buf.copy_from_slice(&self.raw_address[1..]);
+        buf
+    }
+}
+
+impl From<ethabi::Address> for Address {
+    fn from(evm_address: ethabi::Address) -> Self {
+        Address::from_evm(evm_address.0)
+    }
+}
+
+impl From<Address> for ethabi::Address {
+    fn from(address: Address) -> Self {
+        ethabi::Address::from(address.evm_address())
+    }
+}
+
+impl From<&Address> for ethabi::Address {
+    fn from(address: &Address) -> Self {
+        ethabi::Address::from(address.evm_address())
+    }
+}
+
+impl From<Address> for ethabi::Token {
+    fn from(address: Address) -> Self {
+        ethabi::Token::Address(address.into())
+    }
+}
+
+/// 从 ABI 解码结果（如合约事件的参数）中取出地址
+impl TryFrom<ethabi::Token> for Address {
+    type Error = ethabi::Token;
+
+    fn try_from(token: ethabi::Token) -> Result<Self, Self::Error> {
+        match token {
+            ethabi::Token::Address(address) => Ok(address.into()),
+            token => Err(token),
+        }
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    /// 接受 base58（34 个字符）、`41` 开头的十六进制（42 个字符）和 EVM 地址（40 个字符，可以带 `0x` 前缀）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.starts_with("0x") || s.len() == 42 || s.len() == 40 {
+            Address::from_hex(s)
+        } else {
+            Address::from_base58(s)
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_base58())
+    }
+}
+
+/// 序列化为 base58，反序列化时接受 [`FromStr`] 支持的所有格式
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_base58())
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 以 `41` 开头的十六进制序列化地址，用于 `#[serde(with = "tron_core::key::hex_address")]`
+pub mod hex_address {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Address;
+
+    pub fn serialize<S: Serializer>(address: &Address, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&address.to_hex())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+        Address::from_hex(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// 序列化为压缩公钥的十六进制
+impl Serialize for PublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(self.to_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = hex::decode(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)?;
+        PublicKey::from_bytes(&bytes).map_err(serde::de::Error::custom)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use super::*;
-    use sha3::{Keccak256, Digest};
-    use k256::elliptic_curve::sec1::ToEncodedPoint;
 
     #[test]
     fn test_generate_private_key() {
         let private_key = PrivateKey::generate();
-        let bytes = private_key.to_bytes();
+        assert_eq!(PrivateKey::from_bytes(&*private_key.to_secret_bytes()).unwrap(), private_key);
+    }
 
-        assert_eq!(PrivateKey::from_bytes(&bytes).unwrap(), private_key);
+    #[test]
+    fn test_sign_message() {
+        let private_key = PrivateKey::generate();
+        let message = "sign in to example.com, nonce: 8d3f";
+
+        let signature = private_key.sign_message(&message).unwrap();
+        assert_eq!(signature.len(), 65);
+        assert!(signature[64] == 27 || signature[64] == 28);
+        assert!(verify_message(private_key.address(), &message, &signature));
+        assert_eq!(recover_message(&message, &signature).unwrap(), *private_key.address());
+
+        // 摘要包含前缀和消息长度，与交易签名不同
+        let mut prefixed = b"\x19TRON Signed Message:\n35".to_vec();
+        prefixed.extend_from_slice(message.as_bytes());
+        assert_eq!(message_hash(&message), <[u8; 32]>::from(Keccak256::digest(&prefixed)));
+        assert_ne!(&signature[..64], private_key.sign(&message).as_ref()[..64].as_ref());
+
+        assert!(!verify_message(private_key.address(), &"sign in to example.com, nonce: 0000", &signature));
+        assert!(!verify_message(PrivateKey::generate().address(), &message, &signature));
+        assert!(!verify_message(private_key.address(), &message, &signature[..64]));
+    }
+
+    #[test]
+    fn test_sign_message_vectors() {
+        // 按 TronWeb `signMessageV2` 的算法（ethers 的 `signDigest`，RFC 6979）独立计算的向量，
+        // 消息长度按 UTF-8 字节数计算
+        let private_key = PrivateKey::from_bytes(
+            &hex::decode("8e812436a0e3323166e1f0e8ba79e19e217b2c4a53c970d4cca0cfb1078979df").unwrap(),
+        ).unwrap();
+        let address: Address = "THKrowiEfCe8evdbaBzDDvQjM5DGeB3s3F".parse().unwrap();
+        assert_eq!(private_key.address(), &address);
+
+        let vectors = [
+            (
+                "hello world",
+                "cf02daeb2bea196ed5692322a66ed50080ce74ff8cb711199f1b04f3c13bc10d",
+                "e28bb6b7bf55deb691bad220d14a64d17d25d381f795e06fe2dcebaffd68ac09\
+                 37987f488a9a9c38c3f42aa8724de2a89d1916ee10493127c62de8ebc5ed8e9b1b",
+            ),
+            (
+                "TRON 签名测试",
+                "45ab702b298ca34d0bb786db71f8ce33dbb631056620982f78b2d78e8b194c0c",
+                "edcdcc815dafe1914cb31a2ec93e628466288dae8ceb51631ea3944805fdf2a4\
+                 395bd9c09c114aac1c5f007c5a3a31b4dc7fe069db672ebbbb93329ad669b9bd1b",
+            ),
+        ];
+        for (message, hash, signature) in vectors {
+            let signature = hex::decode(signature).unwrap();
+            assert_eq!(hex::encode(message_hash(&message)), hash);
+            assert_eq!(private_key.sign_message(&message).unwrap(), signature);
+            assert_eq!(recover_message(&message, &signature).unwrap(), address);
+            assert!(verify_message(&address, &message, &signature));
+        }
+    }
+
+    #[test]
+    fn test_redacted_debug() {
+        let private_key = PrivateKey::generate();
+        let secret = private_key.private_key_string();
+        assert_eq!(hex::decode(secret.expose_secret()).unwrap(), private_key.to_secret_bytes().to_vec());
+
+        let debug = format!("{:?} {:?}", private_key, secret);
+        assert!(!debug.contains(secret.expose_secret()));
+        assert!(debug.contains(&private_key.address_string()));
+        assert!(debug.contains("<redacted>"));
     }
 
     #[test]
     fn test_address_generate() {
         let mut buf = [0; 32];
-        hex::decode_to_slice("81fb3e8cacea0567c6d76630f825cbcafc6c0e437642c469427118ad196b680e", &mut buf);
+        hex::decode_to_slice("81fb3e8cacea0567c6d76630f825cbcafc6c0e437642c469427118ad196b680e", &mut buf).unwrap();
         let private_key = PrivateKey::from_bytes(&buf).unwrap();
 
         assert_eq!("TD19GP9scAsF5R8Y1TWXNBeSbhavMYjVyP", private_key.address_string());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_public_key() {
+        let private_key = PrivateKey::generate();
+        let public_key = PublicKey::from_bytes(&private_key.public_key().to_bytes()).unwrap();
+        assert_eq!(public_key.address(), private_key.address());
+
+        let hash: [u8; 32] = sha2::Sha256::digest(b"raw data").into();
+        let mut signature = private_key.sign(b"raw data").as_ref().to_vec();
+        assert_eq!(PublicKey::recover(&hash, &signature).unwrap(), public_key);
+
+        // 以太坊风格的 v
+        signature[64] += 27;
+        assert_eq!(PublicKey::recover(&hash, &signature).unwrap(), public_key);
+
+        assert_ne!(PublicKey::recover(&[0; 32], &signature).ok().as_ref(), Some(&public_key));
+        assert!(PublicKey::recover(&hash, &signature[..64]).is_err());
+    }
+
+    #[test]
+    fn test_parse_address() {
+        let base58 = "TD19GP9scAsF5R8Y1TWXNBeSbhavMYjVyP";
+        let address: Address = base58.parse().unwrap();
+        assert_eq!(address.to_string(), base58);
+
+        let hex = address.to_hex();
+        assert!(hex.starts_with("41"));
+        assert_eq!(hex.parse::<Address>().unwrap(), address);
+        assert_eq!(format!("0x{}", &hex[2..]).parse::<Address>().unwrap(), address);
+        assert_eq!(hex[2..].parse::<Address>().unwrap(), address);
+
+        assert!(matches!("TD19GP9scAsF5R8Y1TWXNBeSbhavMYjVyQ".parse::<Address>(), Err(AddressError::InvalidChecksum)));
+        assert!(matches!("TD19GP9scAsF5R8Y1TWX".parse::<Address>(), Err(AddressError::InvalidLength(_))));
+        assert!(matches!("TD19GP9scAsF5R8Y1TWXNBeSbhavMYjVy0".parse::<Address>(), Err(AddressError::Base58(_))));
+        assert!(matches!(format!("42{}", &hex[2..]).parse::<Address>(), Err(AddressError::InvalidPrefix(0x42))));
+        assert!(matches!("0xzz".parse::<Address>(), Err(AddressError::Hex(_))));
+
+        // 以太坊地址的 base58check 编码，前缀不是 0x41
+        let mut buf = vec![0x00; 21];
+        buf.extend_from_slice(&raw_address_base_check(&buf));
+        assert!(matches!(bs58::encode(&buf).into_string().parse::<Address>(), Err(AddressError::InvalidPrefix(0))));
+
+        // 25 字节的地址同样检查前缀和校验和
+        let checked = CheckedAddress::try_from(&bs58::decode(address.to_base58()).into_vec().unwrap()[..]).unwrap();
+        assert_eq!(Address::try_from(checked).unwrap(), address);
+        let mut corrupted = checked;
+        corrupted[24] ^= 1;
+        assert!(matches!(Address::try_from(corrupted), Err(AddressError::InvalidChecksum)));
+        assert!(matches!(Address::try_from(CheckedAddress::try_from(&buf[..]).unwrap()), Err(AddressError::InvalidPrefix(0))));
+    }
+
+    #[test]
+    fn test_address_serde() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Payout {
+            to: Address,
+            #[serde(with = "hex_address")]
+            from: Address,
+        }
+
+        let to: Address = "TD19GP9scAsF5R8Y1TWXNBeSbhavMYjVyP".parse().unwrap();
+        let from = *PrivateKey::generate().address();
+        let payout = Payout { to, from };
+
+        let json = serde_json::to_string(&payout).unwrap();
+        assert_eq!(json, format!(r#"{{"to":"TD19GP9scAsF5R8Y1TWXNBeSbhavMYjVyP","from":"{}"}}"#, from.to_hex()));
+        assert_eq!(serde_json::from_str::<Payout>(&json).unwrap(), payout);
+        assert!(serde_json::from_str::<Address>(r#""TD19GP9scAsF5R8Y1TWXNBeSbhavMYjVyQ""#).is_err());
+
+        let mut balances = HashMap::new();
+        balances.insert(to, 1);
+        assert_eq!(balances[&"TD19GP9scAsF5R8Y1TWXNBeSbhavMYjVyP".parse().unwrap()], 1);
+
+        let public_key = PrivateKey::generate().public_key().clone();
+        let json = serde_json::to_string(&public_key).unwrap();
+        assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), public_key);
+    }
+}
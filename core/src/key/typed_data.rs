@@ -0,0 +1,358 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use ethabi::Uint;
+use serde_json::Value;
+use sha3::{Digest, Keccak256};
+
+use crate::error::Error;
+use crate::key::Address;
+use crate::Result;
+
+const DOMAIN_TYPE: &str = "EIP712Domain";
+
+/// 结构体中的字段
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TypedField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+}
+
+/// TIP-712 结构化数据，JSON 格式与 `eth_signTypedData_v4` 相同
+///
+/// 与 EIP-712 的区别：`address` 可以是 base58 或 `41` 开头的十六进制 TRON 地址，编码时去掉 0x41 前缀；
+/// `trcToken` 按 `uint256` 编码。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    /// 未定义 `EIP712Domain` 时按 `domain` 中出现的字段推断
+    pub types: BTreeMap<String, Vec<TypedField>>,
+    pub primary_type: String,
+    pub domain: Value,
+    pub message: Value,
+}
+
+impl TypedData {
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| Error::TypedData(e.to_string()))
+    }
+
+    /// 域分隔符 `hashStruct(domain)`
+    pub fn domain_separator(&self) -> Result<[u8; 32]> {
+        let mut types = self.types.clone();
+        types.entry(DOMAIN_TYPE.into()).or_insert_with(|| domain_fields(&self.domain));
+
+        Encoder { types: &types }.hash_struct(DOMAIN_TYPE, &self.domain)
+    }
+
+    /// 消息的 `hashStruct(message)`
+    pub fn struct_hash(&self) -> Result<[u8; 32]> {
+        Encoder { types: &self.types }.hash_struct(&self.primary_type, &self.message)
+    }
+
+    /// 签名的摘要 `keccak256("\x19\x01" || domainSeparator || hashStruct(message))`
+    pub fn signing_hash(&self) -> Result<[u8; 32]> {
+        let mut hasher = Keccak256::new();
+        hasher.update([0x19, 0x01]);
+        hasher.update(self.domain_separator()?);
+        hasher.update(self.struct_hash()?);
+
+        Ok(hasher.finalize().into())
+    }
+}
+
+/// 域中允许的字段，按规范的顺序排列
+fn domain_fields(domain: &Value) -> Vec<TypedField> {
+    [
+        ("name", "string"),
+        ("version", "string"),
+        ("chainId", "uint256"),
+        ("verifyingContract", "address"),
+        ("salt", "bytes32"),
+    ]
+    .iter()
+    .filter(|(name, _)| matches!(domain.get(name), Some(value) if !value.is_null()))
+    .map(|(name, r#type)| TypedField { name: (*name).into(), r#type: (*r#type).into() })
+    .collect()
+}
+
+struct Encoder<'a> {
+    types: &'a BTreeMap<String, Vec<TypedField>>,
+}
+
+impl Encoder<'_> {
+    fn fields(&self, name: &str) -> Result<&[TypedField]> {
+        self.types.get(name)
+            .map(|fields| &fields[..])
+            .ok_or_else(|| Error::TypedData(format!("unknown type {}", name)))
+    }
+
+    /// 收集结构体引用的所有结构体类型（包括自身）
+    fn dependencies<'s>(&'s self, name: &'s str, found: &mut BTreeSet<&'s str>) -> Result<()> {
+        if found.contains(name) {
+            return Ok(());
+        }
+        found.insert(name);
+
+        for field in self.fields(name)? {
+            let base = base_type(&field.r#type);
+            if self.types.contains_key(base) {
+                self.dependencies(base, found)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `Mail(Person from,Person to,string contents)Person(string name,address wallet)`
+    fn encode_type(&self, name: &str) -> Result<String> {
+        let mut dependencies = BTreeSet::new();
+        self.dependencies(name, &mut dependencies)?;
+        dependencies.remove(name);
+
+        let mut encoded = String::new();
+        for name in std::iter::once(name).chain(dependencies) {
+            let fields = self.fields(name)?.iter()
+                .map(|field| format!("{} {}", field.r#type, field.name))
+                .collect::<Vec<_>>();
+            encoded.push_str(&format!("{}({})", name, fields.join(",")));
+        }
+
+        Ok(encoded)
+    }
+
+    fn hash_struct(&self, name: &str, value: &Value) -> Result<[u8; 32]> {
+        let mut hasher = Keccak256::new();
+        hasher.update(keccak256(self.encode_type(name)?.as_bytes()));
+
+        for field in self.fields(name)? {
+            let value = value.get(&field.name)
+                .ok_or_else(|| Error::TypedData(format!("missing field {} of {}", field.name, name)))?;
+            hasher.update(self.encode_value(&field.r#type, value)?);
+        }
+
+        Ok(hasher.finalize().into())
+    }
+
+    fn encode_value(&self, r#type: &str, value: &Value) -> Result<[u8; 32]> {
+        if let Some(item_type) = array_item_type(r#type) {
+            let items = value.as_array()
+                .ok_or_else(|| Error::TypedData(format!("expect array for {}", r#type)))?;
+            let mut hasher = Keccak256::new();
+            for item in items {
+                hasher.update(self.encode_value(item_type, item)?);
+            }
+            return Ok(hasher.finalize().into());
+        }
+
+        if self.types.contains_key(r#type) {
+            return self.hash_struct(r#type, value);
+        }
+
+        let mut word = [0; 32];
+        match r#type {
+            "string" => word = keccak256(as_str(r#type, value)?.as_bytes()),
+            "bytes" => word = keccak256(&decode_hex(as_str(r#type, value)?)?),
+            "bool" => {
+                let value = value.as_bool().ok_or_else(|| Error::TypedData(format!("expect bool, got {}", value)))?;
+                word[31] = value as u8;
+            }
+            "address" => {
+                let address = as_str(r#type, value)?
+                    .parse::<Address>()
+                    .map_err(|e| Error::TypedData(format!("invalid address {}: {}", value, e)))?;
+                word[12..].copy_from_slice(&address.evm_address());
+            }
+            "trcToken" => parse_uint(value, 256)?.to_big_endian(&mut word),
+            _ if r#type.starts_with("bytes") => {
+                let size = type_size(r#type, "bytes")?;
+                let bytes = decode_hex(as_str(r#type, value)?)?;
+                if size == 0 || size > 32 || bytes.len() != size {
+                    return Err(Error::TypedData(format!("invalid {} value {}", r#type, value)));
+                }
+                word[..size].copy_from_slice(&bytes);
+            }
+            _ if r#type.starts_with("uint") => parse_uint(value, type_size(r#type, "uint")?)?.to_big_endian(&mut word),
+            _ if r#type.starts_with("int") => parse_int(value, type_size(r#type, "int")?)?.to_big_endian(&mut word),
+            _ => return Err(Error::TypedData(format!("unknown type {}", r#type))),
+        }
+
+        Ok(word)
+    }
+}
+
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// `Person[]`、`Person[2][]` 等去掉所有数组后缀后的类型
+fn base_type(r#type: &str) -> &str {
+    r#type.split('[').next().unwrap_or(r#type)
+}
+
+/// 数组元素的类型，不是数组时返回 `None`
+fn array_item_type(r#type: &str) -> Option<&str> {
+    match r#type.strip_suffix(']') {
+        Some(rest) => rest.rfind('[').map(|index| &rest[..index]),
+        None => None,
+    }
+}
+
+/// `uint64` 等类型的位数或字节数，没有后缀时为 256
+fn type_size(r#type: &str, prefix: &str) -> Result<usize> {
+    match &r#type[prefix.len()..] {
+        "" => Ok(256),
+        size => size.parse().map_err(|_| Error::TypedData(format!("unknown type {}", r#type))),
+    }
+}
+
+fn as_str<'v>(r#type: &str, value: &'v Value) -> Result<&'v str> {
+    value.as_str().ok_or_else(|| Error::TypedData(format!("expect string for {}, got {}", r#type, value)))
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(|e| Error::TypedData(format!("invalid hex {}: {}", s, e)))
+}
+
+/// 数字可以是 JSON 数字、十进制字符串或 `0x` 开头的十六进制字符串
+fn parse_uint(value: &Value, bits: usize) -> Result<Uint> {
+    let number = match value {
+        Value::Number(number) => number.as_u64().map(Uint::from),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => Uint::from_str_radix(hex, 16).ok(),
+            None => Uint::from_dec_str(s).ok(),
+        },
+        _ => None,
+    };
+
+    match number {
+        Some(number) if bits > 0 && bits <= 256 && number.bits() <= bits => Ok(number),
+        _ => Err(Error::TypedData(format!("invalid uint{} value {}", bits, value))),
+    }
+}
+
+/// 负数按 256 位补码编码
+fn parse_int(value: &Value, bits: usize) -> Result<Uint> {
+    let (negative, magnitude) = match value {
+        Value::Number(number) => match number.as_i64() {
+            Some(n) => (n < 0, Value::from(n.unsigned_abs())),
+            None => (false, value.clone()),
+        },
+        Value::String(s) => match s.strip_prefix('-') {
+            Some(rest) => (true, Value::from(rest)),
+            None => (false, value.clone()),
+        },
+        _ => (false, value.clone()),
+    };
+
+    let invalid = || Error::TypedData(format!("invalid int{} value {}", bits, value));
+    let magnitude = parse_uint(&magnitude, 256).map_err(|_| invalid())?;
+    // 正数最多 bits - 1 位，负数的绝对值最大为 2^(bits - 1)
+    let limit = Uint::one() << (bits.clamp(1, 256) - 1);
+    if bits == 0 || bits > 256 || magnitude > limit || (!negative && magnitude == limit) {
+        return Err(invalid());
+    }
+
+    Ok(match negative {
+        true => (!magnitude).overflowing_add(Uint::one()).0,
+        false => magnitude,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::{recover_typed_data, PrivateKey};
+
+    /// EIP-712 规范中的示例
+    const MAIL: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+
+    #[test]
+    fn test_eip712_example() {
+        let typed_data = TypedData::from_json(MAIL).unwrap();
+        let encoder = Encoder { types: &typed_data.types };
+
+        assert_eq!(encoder.encode_type("Mail").unwrap(), "Mail(Person from,Person to,string contents)Person(string name,address wallet)");
+        assert_eq!(hex::encode(typed_data.domain_separator().unwrap()), "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f");
+        assert_eq!(hex::encode(typed_data.struct_hash().unwrap()), "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e");
+        assert_eq!(hex::encode(typed_data.signing_hash().unwrap()), "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2");
+
+        let key = PrivateKey::from_bytes(&keccak256(b"cow")).unwrap();
+        let signature = key.sign_typed_data(&typed_data).unwrap();
+        assert_eq!(
+            hex::encode(&signature),
+            "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d\
+             07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562\
+             1c"
+        );
+        assert_eq!(recover_typed_data(&typed_data, &signature).unwrap(), *key.address());
+    }
+
+    #[test]
+    fn test_tron_types() {
+        let typed_data = TypedData::from_json(MAIL).unwrap();
+        let hash = typed_data.signing_hash().unwrap();
+
+        // TRON 地址只编码后 20 字节，base58 和 41 开头的十六进制与 EVM 地址的结果相同
+        let mut tron = typed_data.clone();
+        let wallet: Address = "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826".parse().unwrap();
+        tron.message["from"]["wallet"] = Value::from(wallet.to_base58());
+        tron.domain["verifyingContract"] = Value::from("41cccccccccccccccccccccccccccccccccccccccc");
+        assert_eq!(tron.signing_hash().unwrap(), hash);
+
+        // 推断的域类型
+        let mut inferred = tron.clone();
+        inferred.types.remove(DOMAIN_TYPE);
+        assert_eq!(inferred.domain_separator().unwrap(), typed_data.domain_separator().unwrap());
+
+        let types = serde_json::from_value(serde_json::json!({
+            "Transfer": [
+                { "name": "token", "type": "trcToken" },
+                { "name": "amount", "type": "uint256" },
+                { "name": "delta", "type": "int8" },
+                { "name": "tags", "type": "bytes4[]" }
+            ]
+        })).unwrap();
+        let encoder = Encoder { types: &types };
+        let message = serde_json::json!({ "token": "1002000", "amount": "0x10", "delta": -1, "tags": ["0xa9059cbb"] });
+
+        assert_eq!(encoder.encode_value("trcToken", &message["token"]).unwrap(), encoder.encode_value("uint256", &Value::from(1002000)).unwrap());
+        assert_eq!(encoder.encode_value("int8", &message["delta"]).unwrap(), [0xff; 32]);
+        assert!(encoder.hash_struct("Transfer", &message).is_ok());
+
+        assert!(encoder.encode_value("int8", &Value::from(128)).is_err());
+        assert!(encoder.encode_value("uint8", &Value::from(256)).is_err());
+        assert!(encoder.encode_value("bytes4", &Value::from("0xa9059c")).is_err());
+        assert!(encoder.hash_struct("Transfer", &serde_json::json!({ "token": 1 })).is_err());
+    }
+}
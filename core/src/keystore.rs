@@ -0,0 +1,380 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use aes::cipher::{NewCipher, StreamCipher};
+use aes::Aes128Ctr;
+use hmac::Hmac;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use crate::error::Error;
+use crate::key::{Address, PrivateKey};
+use crate::Result;
+
+const CIPHER: &str = "aes-128-ctr";
+const PRF: &str = "hmac-sha256";
+const DKLEN: usize = 32;
+
+/// 派生加密密钥的 KDF 及其参数
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kdf", content = "kdfparams", rename_all = "lowercase")]
+pub enum Kdf {
+    Scrypt {
+        dklen: usize,
+        n: u32,
+        r: u32,
+        p: u32,
+        #[serde(with = "hex_bytes")]
+        salt: Vec<u8>,
+    },
+    Pbkdf2 {
+        dklen: usize,
+        c: u32,
+        prf: String,
+        #[serde(with = "hex_bytes")]
+        salt: Vec<u8>,
+    },
+}
+
+impl Kdf {
+    /// 使用随机盐的 scrypt，`n` 必须是 2 的幂
+    pub fn scrypt(n: u32, r: u32, p: u32) -> Self {
+        Kdf::Scrypt { dklen: DKLEN, n, r, p, salt: random_bytes(32) }
+    }
+
+    /// 使用随机盐的 PBKDF2-HMAC-SHA256
+    pub fn pbkdf2(c: u32) -> Self {
+        Kdf::Pbkdf2 { dklen: DKLEN, c, prf: PRF.into(), salt: random_bytes(32) }
+    }
+
+    fn derive_key(&self, password: &str) -> Result<Zeroizing<Vec<u8>>> {
+        match self {
+            Kdf::Scrypt { dklen, n, r, p, salt } => {
+                if *dklen < DKLEN || !n.is_power_of_two() {
+                    return Err(Error::Keystore(format!("unsupported scrypt params: dklen = {}, n = {}", dklen, n)));
+                }
+
+                let params = scrypt::Params::new(n.trailing_zeros() as u8, *r, *p)
+                    .map_err(|e| Error::Keystore(format!("invalid scrypt params: {}", e)))?;
+                let mut key = Zeroizing::new(vec![0; *dklen]);
+                scrypt::scrypt(password.as_bytes(), salt, &params, &mut key)
+                    .map_err(|e| Error::Keystore(format!("scrypt failed: {}", e)))?;
+                Ok(key)
+            }
+            Kdf::Pbkdf2 { dklen, c, prf, salt } => {
+                if *dklen < DKLEN || prf != PRF {
+                    return Err(Error::Keystore(format!("unsupported pbkdf2 params: dklen = {}, prf = {}", dklen, prf)));
+                }
+
+                let mut key = Zeroizing::new(vec![0; *dklen]);
+                pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, *c, &mut key);
+                Ok(key)
+            }
+        }
+    }
+}
+
+/// 与 geth 默认值相同的 scrypt 参数（n = 2^18, r = 8, p = 1）
+impl Default for Kdf {
+    fn default() -> Self {
+        Kdf::scrypt(1 << 18, 8, 1)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CipherParams {
+    #[serde(with = "hex_bytes")]
+    pub iv: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Crypto {
+    pub cipher: String,
+    pub cipherparams: CipherParams,
+    #[serde(with = "hex_bytes")]
+    pub ciphertext: Vec<u8>,
+    #[serde(flatten)]
+    pub kdf: Kdf,
+    #[serde(with = "hex_bytes")]
+    pub mac: Vec<u8>,
+}
+
+/// 以太坊 V3 格式的加密私钥
+///
+/// 私钥使用 AES-128-CTR 加密，密钥由密码经 scrypt 或 PBKDF2 派生，
+/// MAC 为 `keccak256(derived_key[16..32] || ciphertext)`。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Keystore {
+    /// 明文保存的地址，仅用于查找，解密时以私钥计算的地址为准
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<Address>,
+    #[serde(alias = "Crypto")]
+    pub crypto: Crypto,
+    pub id: String,
+    pub version: u32,
+}
+
+impl Keystore {
+    /// 使用默认的 scrypt 参数加密
+    pub fn encrypt(key: &PrivateKey, password: &str) -> Result<Self> {
+        Self::encrypt_with(key, password, Kdf::default())
+    }
+
+    pub fn encrypt_with(key: &PrivateKey, password: &str, kdf: Kdf) -> Result<Self> {
+        let derived_key = kdf.derive_key(password)?;
+        let iv = random_bytes(16);
+
+        let mut ciphertext = key.to_secret_bytes().to_vec();
+        apply_cipher(&derived_key, &iv, &mut ciphertext)?;
+
+        Ok(Keystore {
+            address: Some(*key.address()),
+            crypto: Crypto {
+                cipher: CIPHER.into(),
+                cipherparams: CipherParams { iv },
+                mac: mac(&derived_key, &ciphertext),
+                ciphertext,
+                kdf,
+            },
+            id: uuid::Uuid::new_v4().to_string(),
+            version: 3,
+        })
+    }
+
+    /// 解密私钥，密码错误时返回 [`Error::InvalidPassword`]
+    pub fn decrypt(&self, password: &str) -> Result<PrivateKey> {
+        if self.version != 3 || self.crypto.cipher != CIPHER {
+            return Err(Error::Keystore(format!("unsupported version {} or cipher {}", self.version, self.crypto.cipher)));
+        }
+
+        let derived_key = self.crypto.kdf.derive_key(password)?;
+        // 常数时间比较，避免通过耗时推测 MAC
+        if !bool::from(mac(&derived_key, &self.crypto.ciphertext).ct_eq(&self.crypto.mac)) {
+            return Err(Error::InvalidPassword);
+        }
+
+        let mut plaintext = Zeroizing::new(self.crypto.ciphertext.clone());
+        apply_cipher(&derived_key, &self.crypto.cipherparams.iv, &mut plaintext)?;
+
+        PrivateKey::from_bytes(&plaintext).map_err(|_| Error::Keystore("invalid private key".into()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| Error::Keystore(e.to_string()))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::Keystore(e.to_string()))
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+/// 以目录保存的 keystore，每个私钥一个 `<base58 地址>.json` 文件
+pub struct KeystoreDir {
+    path: PathBuf,
+}
+
+impl KeystoreDir {
+    /// 打开目录，不存在时创建
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let path = path.into();
+        fs::create_dir_all(&path)?;
+
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 目录中所有 keystore 的地址，无法解析的文件会被忽略
+    pub fn list(&self) -> Result<Vec<Address>> {
+        let mut addresses = self.keystores()?
+            .into_iter()
+            .filter_map(|(_, keystore)| keystore.address)
+            .collect::<Vec<_>>();
+        addresses.sort();
+
+        Ok(addresses)
+    }
+
+    /// 使用默认参数加密并保存私钥，返回文件路径
+    pub fn import(&self, key: &PrivateKey, password: &str) -> Result<PathBuf> {
+        self.import_keystore(&Keystore::encrypt(key, password)?)
+    }
+
+    /// 保存已加密的 keystore，返回文件路径
+    ///
+    /// 不覆盖已有的文件，unix 上文件权限为 0600，只有当前用户可以读取。
+    pub fn import_keystore(&self, keystore: &Keystore) -> Result<PathBuf> {
+        let address = keystore.address.ok_or_else(|| Error::Keystore("keystore has no address".into()))?;
+        let path = self.path.join(format!("{}.json", address));
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&path).map_err(|e| match e.kind() {
+            ErrorKind::AlreadyExists => Error::Keystore(format!("keystore {} already exists", path.display())),
+            _ => e.into(),
+        })?;
+        file.write_all(keystore.to_json()?.as_bytes())?;
+
+        Ok(path)
+    }
+
+    /// 解密指定地址的私钥
+    pub fn unlock(&self, address: &Address, password: &str) -> Result<PrivateKey> {
+        let keystore = self.keystores()?
+            .into_iter()
+            .map(|(_, keystore)| keystore)
+            .find(|keystore| keystore.address.as_ref() == Some(address))
+            .ok_or_else(|| Error::Keystore(format!("no keystore for address {}", address)))?;
+
+        let key = keystore.decrypt(password)?;
+        if key.address() != address {
+            return Err(Error::Keystore(format!("keystore address mismatch, decrypted {}", key.address())));
+        }
+
+        Ok(key)
+    }
+
+    fn keystores(&self) -> Result<Vec<(PathBuf, Keystore)>> {
+        let mut keystores = vec![];
+        for entry in fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+
+            match Keystore::from_file(&path) {
+                Ok(keystore) => keystores.push((path, keystore)),
+                Err(e) => debug!("skip keystore file {}: {}", path.display(), e),
+            }
+        }
+
+        Ok(keystores)
+    }
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut buf = vec![0; len];
+    OsRng.fill_bytes(&mut buf);
+    buf
+}
+
+fn apply_cipher(derived_key: &[u8], iv: &[u8], data: &mut [u8]) -> Result<()> {
+    let mut cipher = Aes128Ctr::new_from_slices(&derived_key[..16], iv)
+        .map_err(|_| Error::Keystore(format!("invalid iv length {}", iv.len())))?;
+    cipher.apply_keystream(data);
+
+    Ok(())
+}
+
+fn mac(derived_key: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut hasher = Keccak256::new();
+    hasher.update(&derived_key[16..32]);
+    hasher.update(ciphertext);
+    hasher.finalize().to_vec()
+}
+
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        hex::decode(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Web3 Secret Storage Definition 中的 PBKDF2 测试数据
+    const PBKDF2_KEYSTORE: &str = r#"{
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+            "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+            "kdf": "pbkdf2",
+            "kdfparams": {
+                "c": 262144,
+                "dklen": 32,
+                "prf": "hmac-sha256",
+                "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+            },
+            "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+        },
+        "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version": 3
+    }"#;
+
+    #[test]
+    fn test_decrypt_v3() {
+        let keystore = Keystore::from_json(PBKDF2_KEYSTORE).unwrap();
+        let key = keystore.decrypt("testpassword").unwrap();
+
+        assert_eq!(key.private_key_string().expose_secret(), "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d");
+        assert!(matches!(keystore.decrypt("wrong"), Err(Error::InvalidPassword)));
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let key = PrivateKey::generate();
+        for kdf in [Kdf::scrypt(1 << 10, 8, 1), Kdf::pbkdf2(1024)] {
+            let keystore = Keystore::encrypt_with(&key, "password", kdf).unwrap();
+            let keystore = Keystore::from_json(&keystore.to_json().unwrap()).unwrap();
+
+            assert_eq!(keystore.address.as_ref(), Some(key.address()));
+            assert_eq!(keystore.decrypt("password").unwrap(), key);
+            assert!(keystore.decrypt("passw0rd").is_err());
+        }
+    }
+
+    #[test]
+    fn test_keystore_dir() {
+        let path = std::env::temp_dir().join(format!("tron-keystore-{}", uuid::Uuid::new_v4()));
+        let dir = KeystoreDir::open(&path).unwrap();
+
+        let keys = [PrivateKey::generate(), PrivateKey::generate()];
+        let keystores: Vec<_> = keys.iter()
+            .map(|key| Keystore::encrypt_with(key, "password", Kdf::scrypt(1 << 10, 8, 1)).unwrap())
+            .collect();
+        for keystore in &keystores {
+            let file = dir.import_keystore(keystore).unwrap();
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                assert_eq!(fs::metadata(&file).unwrap().permissions().mode() & 0o777, 0o600);
+            }
+        }
+        // 不覆盖已有的 keystore
+        assert!(matches!(dir.import_keystore(&keystores[0]), Err(Error::Keystore(_))));
+        fs::write(path.join("README"), "not a keystore").unwrap();
+
+        let mut addresses = vec![*keys[0].address(), *keys[1].address()];
+        addresses.sort();
+        assert_eq!(dir.list().unwrap(), addresses);
+
+        assert_eq!(dir.unlock(keys[1].address(), "password").unwrap(), keys[1]);
+        assert!(matches!(dir.unlock(keys[1].address(), "wrong"), Err(Error::InvalidPassword)));
+        assert!(dir.unlock(PrivateKey::generate().address(), "password").is_err());
+
+        fs::remove_dir_all(path).unwrap();
+    }
+}
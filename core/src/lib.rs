@@ -1,3 +1,6 @@
+// tonic::Status 体积较大，但作为 gRPC 调用的错误类型无法避免
+#![allow(clippy::result_large_err)]
+
 #[macro_use]
 extern crate async_trait;
 #[macro_use]
@@ -10,9 +13,17 @@ pub mod apis;
 pub mod utils;
 pub mod services;
 pub mod predefined;
+pub mod contract;
 pub mod error;
+pub mod transaction;
+pub mod signer;
+pub mod hd;
+pub mod keystore;
+
+pub use error::Error;
+pub use ethabi;
 
-pub use anyhow::Result;
+pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[cfg(test)]
 mod tests {
@@ -1,31 +1,144 @@
-use ethabi::{Function, Param, ParamType, StateMutability, Token};
+use std::convert::TryFrom;
+
+use ethabi::{Event, EventParam, Function, Param, ParamType, StateMutability, Token, Uint};
+use crate::apis::transaction_info::Log;
+use crate::contract::decode_log;
 use crate::key::Address;
 
-pub fn tether_transfer(address: &Address, amount: i64) -> Vec<u8> {
-    let mut buf = [0; 20];
-    buf.copy_from_slice(&address[1..]);
+/// 构造合约函数的 ABI，参数名为空
+#[allow(deprecated)]
+pub fn function(name: &str, inputs: Vec<ParamType>, outputs: Vec<ParamType>, state_mutability: StateMutability) -> Function {
+    let params = |kinds: Vec<ParamType>| kinds.into_iter()
+        .map(|kind| Param { name: String::new(), kind })
+        .collect();
+
     Function {
-        name: "transfer".into(),
+        name: name.into(),
+        inputs: params(inputs),
+        outputs: params(outputs),
+        constant: matches!(state_mutability, StateMutability::View | StateMutability::Pure),
+        state_mutability,
+    }
+}
+
+/// TRC20 标准中的函数，不是标准函数时返回 `None`
+pub fn trc20_function(name: &str) -> Option<Function> {
+    use ParamType::{Address, Bool, String, Uint};
+    use StateMutability::{NonPayable, View};
+
+    let (inputs, outputs, state_mutability) = match name {
+        "name" | "symbol" => (vec![], vec![String], View),
+        "decimals" => (vec![], vec![Uint(8)], View),
+        "totalSupply" => (vec![], vec![Uint(256)], View),
+        "balanceOf" => (vec![Address], vec![Uint(256)], View),
+        "allowance" => (vec![Address, Address], vec![Uint(256)], View),
+        "transfer" | "approve" => (vec![Address, Uint(256)], vec![Bool], NonPayable),
+        "transferFrom" => (vec![Address, Address, Uint(256)], vec![Bool], NonPayable),
+        _ => return None,
+    };
+
+    Some(function(name, inputs, outputs, state_mutability))
+}
+
+#[deprecated(note = "use `services::Trc20Token::transfer`, which takes a uint256 amount")]
+pub fn tether_transfer(address: &Address, amount: i64) -> Vec<u8> {
+    trc20_function("transfer")
+        .unwrap()
+        .encode_input(&[Token::from(*address), Token::Uint(amount.into())])
+        .unwrap()
+}
+
+/// TRC20 的 `Transfer(address,address,uint256)` 事件
+pub fn trc20_transfer_event() -> Event {
+    Event {
+        name: "Transfer".into(),
         inputs: vec![
-            Param {
-                name: "_to".into(),
-                kind: ParamType::Address,
-            },
-            Param {
-                name: "_value".into(),
-                kind: ParamType::Uint(256),
-            }
+            EventParam { name: "from".into(), kind: ParamType::Address, indexed: true },
+            EventParam { name: "to".into(), kind: ParamType::Address, indexed: true },
+            EventParam { name: "value".into(), kind: ParamType::Uint(256), indexed: false },
         ],
-        outputs: vec![
-            Param {
-                name: String::new(),
-                kind: ParamType::Bool
-            }
-        ],
-        state_mutability: StateMutability::NonPayable,
-        constant: false
-    }.encode_input(&[
-        Token::Address(buf.into()),
-        Token::Uint(amount.into())
-    ]).unwrap()
-}
\ No newline at end of file
+        anonymous: false,
+    }
+}
+
+/// 解码后的 TRC20 转账事件
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferEvent {
+    /// 代币合约地址
+    pub contract: Address,
+    pub from: Address,
+    pub to: Address,
+    pub value: Uint,
+}
+
+/// 从 `TransactionInfo.log` 中解码 TRC20 转账事件，不是转账事件时返回 `None`
+///
+/// 日志中的地址都是去掉 0x41 前缀的 20 字节 EVM 地址。
+pub fn decode_trc20_transfer(log: &Log) -> Option<TransferEvent> {
+    if log.address.len() != 20 {
+        return None;
+    }
+
+    let mut params = decode_log(&trc20_transfer_event(), log)?.into_iter();
+
+    let contract = Address::from(ethabi::Address::from_slice(&log.address));
+    let from = Address::try_from(params.next()?).ok()?;
+    let to = Address::try_from(params.next()?).ok()?;
+    let value = params.next()?.into_uint()?;
+
+    Some(TransferEvent { contract, from, to, value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trc20_function() {
+        let selector = |name: &str| {
+            let function = trc20_function(name).unwrap();
+            let args: Vec<_> = function.inputs.iter()
+                .map(|param| match param.kind {
+                    ParamType::Address => Token::Address(Default::default()),
+                    _ => Token::Uint(Uint::zero()),
+                })
+                .collect();
+            hex::encode(&function.encode_input(&args).unwrap()[..4])
+        };
+        assert_eq!(selector("balanceOf"), "70a08231");
+        assert_eq!(selector("totalSupply"), "18160ddd");
+        assert_eq!(selector("decimals"), "313ce567");
+        assert_eq!(selector("transferFrom"), "23b872dd");
+        assert_eq!(selector("approve"), "095ea7b3");
+        assert!(trc20_function("mint").is_none());
+    }
+
+    #[test]
+    fn test_decode_trc20_transfer() {
+        let contract: Address = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".parse().unwrap();
+        let from: Address = "TD19GP9scAsF5R8Y1TWXNBeSbhavMYjVyP".parse().unwrap();
+        let to = Address::from_evm([0x11; 20]);
+
+        #[allow(deprecated)]
+        let data = tether_transfer(&to, 1_000000);
+        assert_eq!(hex::encode(&data[..4]), "a9059cbb");
+        assert_eq!(&data[16..36], &to.evm_address());
+
+        let topic = |address: &Address| {
+            let mut topic = vec![0; 12];
+            topic.extend_from_slice(&address.evm_address());
+            topic
+        };
+        let log = Log {
+            address: contract.evm_address().to_vec(),
+            topics: vec![trc20_transfer_event().signature().as_bytes().to_vec(), topic(&from), topic(&to)],
+            data: ethabi::encode(&[Token::Uint(1_000000.into())]),
+        };
+
+        let event = decode_trc20_transfer(&log).unwrap();
+        assert_eq!(event, TransferEvent { contract, from, to, value: 1_000000.into() });
+        assert_eq!(event.to.to_string(), to.to_base58());
+
+        assert!(decode_trc20_transfer(&Log { topics: vec![], ..log }).is_none());
+    }
+}
@@ -1,45 +1,287 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use ethabi::{Function, Token};
 use tonic::transport::Channel;
+use tonic::{Response, Status};
 
 pub use transfer::Transfer;
 pub use resource::Resource;
+pub use config::{ServiceConfig, TlsConfig, Http2Config, PoolConfig, Routing};
+pub use pool::{NodePool, NodeStatus};
+pub use retry::RetryPolicy;
+pub use options::{TxOptions, DEFAULT_FEE_LIMIT};
+pub use multisig::{MultiSig, SignWeight};
+pub use trc20::{Trc20Token, TokenAmount};
+pub use estimate::{Estimate, CostEstimate, FEE_LIMIT_MARGIN};
+pub use permission::{Permissions, PermissionExt, AccountPermissions, MAX_PERMISSION_KEYS, MAX_ACTIVE_PERMISSIONS, DEFAULT_ACTIVE_OPERATIONS};
 
+use crate::apis::r#return::ResponseCode;
 use crate::apis::wallet_client::WalletClient;
-use crate::key::PrivateKey;
+use crate::apis::{BlockReference, BytesMessage, EmptyMessage, Return, SmartContract, Transaction, TransactionExtention, TriggerSmartContract};
+use crate::error::{Error, NodeError};
+use crate::key::{Address, PrivateKey};
+use crate::signer::{sign_transaction, Signer};
+use crate::transaction::{BroadcastReceipt, SignedTransaction};
+use crate::utils::transaction_id;
 use crate::Result;
 
 mod transfer;
 mod resource;
+mod config;
+mod pool;
+mod retry;
+mod options;
+mod multisig;
+mod permission;
+mod trc20;
+mod estimate;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
 
-pub const DEFAULT_ENDPOINT: &'static str = "http://34.253.187.192:50051";
+pub const DEFAULT_ENDPOINT: &str = "http://34.253.187.192:50051";
 
-pub struct ServiceAgent<'s> {
-    key: PrivateKey,
-    client: &'s mut WalletClient<Channel>,
+pub struct ServiceAgent<'s, S = PrivateKey> {
+    signer: S,
+    pool: &'s NodePool,
+    retry: RetryPolicy,
+    options: TxOptions,
 }
 
 pub struct Service {
-    client: WalletClient<Channel>,
+    pool: Arc<NodePool>,
+    retry: RetryPolicy,
 }
 
 impl Service {
     pub async fn new() -> Result<Self> {
-        info!("Connect to block chain endpoint: {}", DEFAULT_ENDPOINT);
+        Self::with_config(ServiceConfig::default()).await
+    }
 
-        Ok(Self {
-            client: WalletClient::connect(DEFAULT_ENDPOINT).await?,
-        })
+    pub async fn with_config(config: ServiceConfig) -> Result<Self> {
+        Self::with_pool_config(config.into()).await
     }
 
-    pub fn agent(&mut self, key: PrivateKey) -> ServiceAgent {
+    /// 连接到多个节点，全节点或 solidity 节点多于一个时会启动后台健康检查
+    pub async fn with_pool_config(config: PoolConfig) -> Result<Self> {
+        for endpoint in &config.endpoints {
+            info!("Connect to block chain endpoint: {}", endpoint.endpoint);
+        }
+        for endpoint in &config.solidity_endpoints {
+            info!("Connect to block chain solidity endpoint: {}", endpoint.endpoint);
+        }
+
+        let pool = Arc::new(NodePool::connect(&config).await?);
+        if (pool.len() > 1 || pool.solidity_len() > 1) && !pool.health_check_interval().is_zero() {
+            pool.spawn_health_check();
+        }
+
+        Ok(Self { pool, retry: config.retry })
+    }
+
+    pub fn pool(&self) -> &Arc<NodePool> {
+        &self.pool
+    }
+
+    pub fn agent<S: Signer>(&self, signer: S) -> ServiceAgent<'_, S> {
         ServiceAgent {
-            client: &mut self.client,
-            key
+            pool: &self.pool,
+            retry: self.retry.clone(),
+            options: TxOptions::default(),
+            signer
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct ServiceConfig {
-    /// 区块链服务节点
-    pub endpoint: String,
-}
\ No newline at end of file
+impl<'s, S: Signer> ServiceAgent<'s, S> {
+    pub fn signer(&self) -> &S {
+        &self.signer
+    }
+
+    /// 替换从 [`Service`] 继承的重试策略
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// 设置默认的交易选项，调用时传入的选项优先
+    pub fn with_options(mut self, options: TxOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn options(&self) -> &TxOptions {
+        &self.options
+    }
+
+    /// 通过连接池执行调用，并按重试策略重试
+    async fn call<Req, Res, F, Fut>(&self, request: Req, f: F) -> std::result::Result<Response<Res>, Status>
+        where Req: Clone,
+              F: Fn(WalletClient<Channel>, Req) -> Fut,
+              Fut: Future<Output = std::result::Result<Response<Res>, Status>>
+    {
+        self.retry.retry(|| self.pool.call(request.clone(), &f)).await
+    }
+
+    /// 以最新区块作为引用区块，供 [`TransactionBuilder`](crate::transaction::TransactionBuilder) 离线构造交易
+    pub async fn block_reference(&self) -> Result<BlockReference> {
+        let block = self
+            .call(EmptyMessage {}, |mut client, request| async move { client.get_now_block2(request).await })
+            .await?
+            .into_inner();
+
+        Ok(BlockReference::from(&block))
+    }
+
+    /// 检查节点创建的交易并应用交易选项，返回待签名的交易
+    ///
+    /// 交易 ID 在本地计算，应用选项前会先与节点返回的 `txid` 比对，确保签名的正是节点创建的交易。
+    pub fn build(&self, transaction_ext: TransactionExtention, options: &TxOptions) -> Result<Transaction> {
+        debug!("created transaction, id = {}", hex::encode(&transaction_ext.txid));
+        // 节点校验失败时返回的交易为空或无效，不能签名
+        Error::check_transaction(&transaction_ext)?;
+
+        let mut transaction = transaction_ext.transaction.ok_or(Error::EmptyTransaction)?;
+        let raw_data = transaction.raw_data.as_mut().ok_or(Error::EmptyTransaction)?;
+
+        let created_txid = transaction_id(raw_data);
+        if !transaction_ext.txid.is_empty() && transaction_ext.txid[..] != created_txid[..] {
+            return Err(Error::TxidMismatch {
+                expected: hex::encode(&transaction_ext.txid),
+                actual: hex::encode(created_txid),
+            });
+        }
+
+        self.options.merge(options).apply(raw_data)?;
+
+        Ok(transaction)
+    }
+
+    /// 以签名者为调用者执行合约的只读调用，返回合约的返回数据
+    ///
+    /// 合约回滚时返回 [`Error::Revert`]，其中包含解码后的 `Error(string)` 或 `Panic(uint256)`。
+    pub async fn call_constant(&self, contract: &Address, data: Vec<u8>) -> Result<Vec<u8>> {
+        let transaction_ext = self
+            .call(self.constant_request(contract, data), |mut client, request| async move {
+                client.trigger_constant_contract(request).await
+            })
+            .await?
+            .into_inner();
+
+        Error::check_constant_result(&transaction_ext)
+    }
+
+    /// 与 [`call_constant`](Self::call_constant) 相同，但在 solidity 节点上通过 `WalletSolidity` 服务在已固化的状态上执行
+    ///
+    /// 需要在 [`PoolConfig::solidity_endpoints`] 或 [`ServiceConfig::solidity_endpoint`] 中配置 solidity 节点。
+    pub async fn call_constant_solidity(&self, contract: &Address, data: Vec<u8>) -> Result<Vec<u8>> {
+        let request = self.constant_request(contract, data);
+        let transaction_ext = self.retry
+            .retry(|| self.pool.call_solidity(request.clone(), |mut client, request| async move {
+                client.trigger_constant_contract(request).await
+            }))
+            .await?
+            .into_inner();
+
+        Error::check_constant_result(&transaction_ext)
+    }
+
+    /// 只读调用合约函数，按函数的输出参数解码返回值
+    pub async fn call_function(&self, contract: &Address, function: &Function, tokens: &[Token]) -> Result<Vec<Token>> {
+        let output = self.call_constant(contract, function.encode_input(tokens)?).await?;
+
+        Ok(function.decode_output(&output)?)
+    }
+
+    fn constant_request(&self, contract: &Address, data: Vec<u8>) -> TriggerSmartContract {
+        TriggerSmartContract {
+            owner_address: self.signer.address().into(),
+            contract_address: contract.into(),
+            data,
+            ..Default::default()
+        }
+    }
+
+    /// 查询链上的合约，合约不存在时返回 [`Error::ContractNotFound`]
+    pub async fn get_contract(&self, contract: &Address) -> Result<SmartContract> {
+        let request = BytesMessage { value: contract.into() };
+        let smart_contract = self
+            .call(request, |mut client, request| async move { client.get_contract(request).await })
+            .await?
+            .into_inner();
+
+        if smart_contract.contract_address.is_empty() {
+            return Err(Error::ContractNotFound(contract.to_string()));
+        }
+
+        Ok(smart_contract)
+    }
+
+    /// 使用当前的签名者对交易签名
+    pub async fn sign(&self, transaction: Transaction) -> Result<SignedTransaction> {
+        let signed = sign_transaction(&self.signer, transaction).await?;
+        info!("signed transaction, id = {}", signed.txid_hex());
+
+        Ok(signed)
+    }
+
+    /// 广播已签名的交易
+    ///
+    /// 广播不能盲目重试：每次只发送到一个节点，重试前先通过 `get_transaction_by_id` 确认交易是否已经上链，
+    /// 重试时节点返回 `DUP_TRANSACTION_ERROR` 说明之前的广播已被接收，同样视为成功。
+    /// 无法确认交易是否上链时不再重发，返回 [`Error::BroadcastUnknown`]。
+    pub async fn broadcast_transaction(&self, signed: SignedTransaction) -> Result<BroadcastReceipt> {
+        let SignedTransaction { txid, transaction } = signed;
+        let mut attempt = 1;
+
+        let ret = loop {
+            let result = self.pool
+                .call_once(transaction.clone(), |mut client, transaction| async move { client.broadcast_transaction(transaction).await })
+                .await;
+
+            match result {
+                Ok(response) => {
+                    let ret = response.into_inner();
+                    if attempt > 1 && ret.code == ResponseCode::DupTransactionError as i32 {
+                        info!("transaction {} has been accepted by previous broadcast", hex::encode(txid));
+                        break Return { result: true, ..Default::default() };
+                    }
+
+                    break ret;
+                }
+                Err(status) if attempt < self.retry.max_attempts && self.retry.is_retryable(&status) => {
+                    let backoff = self.retry.backoff(attempt);
+                    debug!("broadcast attempt {} failed: {}, retry after {:?}", attempt, status, backoff);
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+
+                    if self.transaction_exists(&txid).await? {
+                        info!("transaction {} has been found on chain, skip broadcasting", hex::encode(txid));
+                        break Return { result: true, ..Default::default() };
+                    }
+                }
+                Err(status) => return Err(status.into()),
+            }
+        };
+
+        NodeError::check(&ret).map_err(Error::Broadcast)?;
+
+        Ok(BroadcastReceipt {
+            txid,
+            signed_transaction: transaction,
+            r#return: ret,
+        })
+    }
+
+    async fn transaction_exists(&self, txid: &[u8]) -> Result<bool> {
+        let request = BytesMessage { value: txid.to_vec() };
+
+        match self.pool.call(request, |mut client, request| async move { client.get_transaction_by_id(request).await }).await {
+            Ok(transaction) => Ok(transaction.get_ref().raw_data.is_some()),
+            Err(status) => {
+                warn!("check transaction {} failed: {}", hex::encode(txid), status);
+                Err(Error::BroadcastUnknown { txid: hex::encode(txid), source: status })
+            }
+        }
+    }
+}
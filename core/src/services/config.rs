@@ -0,0 +1,373 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint};
+
+use crate::error::Error;
+use crate::Result;
+use crate::services::{RetryPolicy, DEFAULT_ENDPOINT};
+
+/// 环境变量前缀，见 [`ServiceConfig::from_env`]
+pub const ENV_PREFIX: &str = "TRON_";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ServiceConfig {
+    /// 区块链服务节点
+    pub endpoint: String,
+    /// 同一节点的 solidity 服务地址（java-tron 默认端口 50061），其余连接参数与 `endpoint` 相同
+    pub solidity_endpoint: Option<String>,
+    /// 建立连接的超时时间（毫秒）
+    pub connect_timeout: Option<u64>,
+    /// 单次请求的超时时间（毫秒）
+    pub timeout: Option<u64>,
+    /// TCP keep-alive 间隔（毫秒）
+    pub tcp_keepalive: Option<u64>,
+    /// TLS 配置，为空时使用明文连接
+    pub tls: Option<TlsConfig>,
+    /// HTTP/2 配置
+    pub http2: Http2Config,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// 是否启用 TLS
+    pub enabled: bool,
+    /// 自定义 CA 证书（PEM 格式）路径，为空时使用系统根证书
+    pub ca_certificate: Option<PathBuf>,
+    /// 校验证书时使用的域名，为空时使用 endpoint 中的域名
+    pub domain: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Http2Config {
+    /// HTTP/2 PING 帧发送间隔（毫秒）
+    pub keep_alive_interval: Option<u64>,
+    /// 等待 PING 响应的超时时间（毫秒）
+    pub keep_alive_timeout: Option<u64>,
+    /// 连接空闲时是否仍然发送 PING
+    pub keep_alive_while_idle: Option<bool>,
+    /// 流级别的初始窗口大小
+    pub initial_stream_window_size: Option<u32>,
+    /// 连接级别的初始窗口大小
+    pub initial_connection_window_size: Option<u32>,
+}
+
+impl Default for ServiceConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: DEFAULT_ENDPOINT.into(),
+            solidity_endpoint: None,
+            connect_timeout: None,
+            timeout: None,
+            tcp_keepalive: None,
+            tls: None,
+            http2: Http2Config::default(),
+        }
+    }
+}
+
+impl ServiceConfig {
+    pub fn new<S: Into<String>>(endpoint: S) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_toml_str(s: &str) -> Result<Self> {
+        from_toml_str(s)
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self> {
+        from_json_str(s)
+    }
+
+    /// 从配置文件中加载，根据扩展名选择 TOML 或 JSON 格式
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        from_file(path.as_ref())
+    }
+
+    /// 从环境变量中加载，未设置的项使用默认值
+    ///
+    /// 支持的变量：`TRON_ENDPOINT`、`TRON_SOLIDITY_ENDPOINT`、`TRON_CONNECT_TIMEOUT`、`TRON_TIMEOUT`、`TRON_TCP_KEEPALIVE`、
+    /// `TRON_TLS`、`TRON_TLS_CA_CERTIFICATE`、`TRON_TLS_DOMAIN`、`TRON_HTTP2_KEEP_ALIVE_INTERVAL`、
+    /// `TRON_HTTP2_KEEP_ALIVE_TIMEOUT`、`TRON_HTTP2_KEEP_ALIVE_WHILE_IDLE`、
+    /// `TRON_HTTP2_INITIAL_STREAM_WINDOW_SIZE`、`TRON_HTTP2_INITIAL_CONNECTION_WINDOW_SIZE`。
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(std::env::vars())
+    }
+
+    fn from_vars<I: IntoIterator<Item = (String, String)>>(vars: I) -> Result<Self> {
+        let mut config = Self::default();
+
+        for (name, value) in vars {
+            let name = match name.strip_prefix(ENV_PREFIX) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            match name {
+                "ENDPOINT" => config.endpoint = value,
+                "SOLIDITY_ENDPOINT" => config.solidity_endpoint = Some(value),
+                "CONNECT_TIMEOUT" => config.connect_timeout = Some(parse_var(name, &value)?),
+                "TIMEOUT" => config.timeout = Some(parse_var(name, &value)?),
+                "TCP_KEEPALIVE" => config.tcp_keepalive = Some(parse_var(name, &value)?),
+                "TLS" => config.tls.get_or_insert_with(Default::default).enabled = parse_var(name, &value)?,
+                "TLS_CA_CERTIFICATE" => config.tls.get_or_insert_with(Default::default).ca_certificate = Some(value.into()),
+                "TLS_DOMAIN" => config.tls.get_or_insert_with(Default::default).domain = Some(value),
+                "HTTP2_KEEP_ALIVE_INTERVAL" => config.http2.keep_alive_interval = Some(parse_var(name, &value)?),
+                "HTTP2_KEEP_ALIVE_TIMEOUT" => config.http2.keep_alive_timeout = Some(parse_var(name, &value)?),
+                "HTTP2_KEEP_ALIVE_WHILE_IDLE" => config.http2.keep_alive_while_idle = Some(parse_var(name, &value)?),
+                "HTTP2_INITIAL_STREAM_WINDOW_SIZE" => config.http2.initial_stream_window_size = Some(parse_var(name, &value)?),
+                "HTTP2_INITIAL_CONNECTION_WINDOW_SIZE" => config.http2.initial_connection_window_size = Some(parse_var(name, &value)?),
+                _ => {}
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// 根据配置构造 tonic 的 [`Endpoint`]
+    pub fn to_endpoint(&self) -> Result<Endpoint> {
+        let mut endpoint = Endpoint::from_shared(self.endpoint.clone())
+            .map_err(|e| Error::InvalidConfig(format!("invalid endpoint {}: {}", self.endpoint, e)))?
+            .tcp_keepalive(self.tcp_keepalive.map(Duration::from_millis))
+            .initial_stream_window_size(self.http2.initial_stream_window_size)
+            .initial_connection_window_size(self.http2.initial_connection_window_size);
+
+        if let Some(timeout) = self.timeout {
+            endpoint = endpoint.timeout(Duration::from_millis(timeout));
+        }
+        if let Some(interval) = self.http2.keep_alive_interval {
+            endpoint = endpoint.http2_keep_alive_interval(Duration::from_millis(interval));
+        }
+        if let Some(timeout) = self.http2.keep_alive_timeout {
+            endpoint = endpoint.keep_alive_timeout(Duration::from_millis(timeout));
+        }
+        if let Some(enabled) = self.http2.keep_alive_while_idle {
+            endpoint = endpoint.keep_alive_while_idle(enabled);
+        }
+
+        match &self.tls {
+            Some(tls) if tls.enabled => {
+                let mut tls_config = ClientTlsConfig::new();
+                if let Some(path) = &tls.ca_certificate {
+                    tls_config = tls_config.ca_certificate(Certificate::from_pem(fs::read(path)?));
+                }
+                if let Some(domain) = &tls.domain {
+                    tls_config = tls_config.domain_name(domain.clone());
+                }
+                endpoint = endpoint.tls_config(tls_config)?;
+            }
+            _ => {}
+        }
+
+        Ok(endpoint)
+    }
+
+    /// 连接到配置的节点
+    pub async fn connect(&self) -> Result<Channel> {
+        let endpoint = self.to_endpoint()?;
+
+        let channel = match self.connect_timeout {
+            Some(timeout) => tokio::time::timeout(Duration::from_millis(timeout), endpoint.connect())
+                .await
+                .map_err(|_| Error::ConnectTimeout(self.endpoint.clone()))??,
+            None => endpoint.connect().await?,
+        };
+
+        Ok(channel)
+    }
+}
+
+/// 多节点连接池的路由策略
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Routing {
+    /// 在可用节点间轮询
+    #[default]
+    RoundRobin,
+    /// 按平均延迟的倒数加权随机选择节点
+    LatencyWeighted,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PoolConfig {
+    /// 节点列表
+    pub endpoints: Vec<ServiceConfig>,
+    /// 提供 `WalletSolidity` 服务的 solidity 节点列表，为空时不能查询已固化的状态
+    pub solidity_endpoints: Vec<ServiceConfig>,
+    /// 路由策略
+    pub routing: Routing,
+    /// 允许落后于最高节点的区块数，超过后节点被剔除，直到追上为止
+    pub max_block_lag: i64,
+    /// 健康检查间隔（毫秒），为 0 时不进行后台检查
+    pub health_check_interval: u64,
+    /// RPC 调用的重试策略
+    pub retry: RetryPolicy,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: vec![],
+            solidity_endpoints: vec![],
+            routing: Routing::default(),
+            max_block_lag: 10,
+            health_check_interval: 10_000,
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+impl From<ServiceConfig> for PoolConfig {
+    fn from(config: ServiceConfig) -> Self {
+        let solidity_endpoints = config.solidity_endpoint.iter()
+            .map(|endpoint| ServiceConfig {
+                endpoint: endpoint.clone(),
+                solidity_endpoint: None,
+                ..config.clone()
+            })
+            .collect();
+
+        Self {
+            endpoints: vec![config],
+            solidity_endpoints,
+            ..Default::default()
+        }
+    }
+}
+
+impl PoolConfig {
+    pub fn from_toml_str(s: &str) -> Result<Self> {
+        from_toml_str(s)
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self> {
+        from_json_str(s)
+    }
+
+    /// 从配置文件中加载，根据扩展名选择 TOML 或 JSON 格式
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        from_file(path.as_ref())
+    }
+}
+
+fn from_toml_str<T: DeserializeOwned>(s: &str) -> Result<T> {
+    toml::from_str(s).map_err(|e| Error::InvalidConfig(e.to_string()))
+}
+
+fn from_json_str<T: DeserializeOwned>(s: &str) -> Result<T> {
+    serde_json::from_str(s).map_err(|e| Error::InvalidConfig(e.to_string()))
+}
+
+fn from_file<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let content = fs::read_to_string(path)?;
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => from_toml_str(&content),
+        Some("json") => from_json_str(&content),
+        _ => Err(Error::InvalidConfig(format!("unsupported config file: {}", path.display()))),
+    }
+}
+
+fn parse_var<T: std::str::FromStr>(name: &str, value: &str) -> Result<T> {
+    value.parse()
+        .map_err(|_| Error::InvalidConfig(format!("invalid value of {}{}: {}", ENV_PREFIX, name, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml() {
+        let config = ServiceConfig::from_toml_str(r#"
+            endpoint = "https://grpc.shasta.trongrid.io:50051"
+            timeout = 5000
+
+            [tls]
+            enabled = true
+            domain = "grpc.shasta.trongrid.io"
+
+            [http2]
+            keep_alive_interval = 30000
+        "#).unwrap();
+
+        assert_eq!(config.endpoint, "https://grpc.shasta.trongrid.io:50051");
+        assert_eq!(config.timeout, Some(5000));
+        assert_eq!(config.connect_timeout, None);
+        assert_eq!(config.tls.as_ref().unwrap().domain.as_deref(), Some("grpc.shasta.trongrid.io"));
+        assert_eq!(config.http2.keep_alive_interval, Some(30000));
+        assert!(config.to_endpoint().is_ok());
+    }
+
+    #[test]
+    fn test_from_json() {
+        let config = ServiceConfig::from_json_str(r#"{"endpoint": "http://127.0.0.1:50051", "connect_timeout": 1000}"#).unwrap();
+
+        assert_eq!(config, ServiceConfig {
+            connect_timeout: Some(1000),
+            ..ServiceConfig::new("http://127.0.0.1:50051")
+        });
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let config = ServiceConfig::from_vars(vec![
+            ("TRON_ENDPOINT".to_string(), "http://127.0.0.1:50051".to_string()),
+            ("TRON_SOLIDITY_ENDPOINT".to_string(), "http://127.0.0.1:50061".to_string()),
+            ("TRON_TIMEOUT".to_string(), "3000".to_string()),
+            ("TRON_TLS".to_string(), "false".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+        ]).unwrap();
+
+        assert_eq!(config.endpoint, "http://127.0.0.1:50051");
+        assert_eq!(config.timeout, Some(3000));
+        assert!(!config.tls.as_ref().unwrap().enabled);
+
+        let pool = PoolConfig::from(config);
+        assert_eq!(pool.endpoints[0].endpoint, "http://127.0.0.1:50051");
+        assert_eq!(pool.solidity_endpoints.len(), 1);
+        assert_eq!(pool.solidity_endpoints[0].endpoint, "http://127.0.0.1:50061");
+        assert_eq!(pool.solidity_endpoints[0].timeout, Some(3000));
+
+        assert!(ServiceConfig::from_vars(vec![("TRON_TIMEOUT".to_string(), "soon".to_string())]).is_err());
+    }
+
+    #[test]
+    fn test_pool_from_toml() {
+        let config = PoolConfig::from_toml_str(r#"
+            routing = "latency_weighted"
+            max_block_lag = 5
+
+            [retry]
+            max_attempts = 5
+
+            [[endpoints]]
+            endpoint = "http://127.0.0.1:50051"
+
+            [[endpoints]]
+            endpoint = "http://127.0.0.1:50052"
+            timeout = 3000
+
+            [[solidity_endpoints]]
+            endpoint = "http://127.0.0.1:50061"
+        "#).unwrap();
+
+        assert_eq!(config.routing, Routing::LatencyWeighted);
+        assert_eq!(config.max_block_lag, 5);
+        assert_eq!(config.health_check_interval, 10_000);
+        assert_eq!(config.endpoints.len(), 2);
+        assert_eq!(config.endpoints[1].timeout, Some(3000));
+        assert_eq!(config.solidity_endpoints[0].endpoint, "http://127.0.0.1:50061");
+        assert_eq!(config.retry.max_attempts, 5);
+        assert_eq!(config.retry.initial_backoff, RetryPolicy::default().initial_backoff);
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use prost::Message;
+
+use crate::apis::{Account, AccountResourceMessage, EmptyMessage, Transaction, TriggerSmartContract};
+use crate::error::Error;
+use crate::key::Address;
+use crate::Result;
+use crate::services::{ServiceAgent, Transfer, TxOptions, DEFAULT_FEE_LIMIT};
+use crate::signer::Signer;
+use crate::transaction::BroadcastReceipt;
+
+/// 自动设置的手续费上限在估算的能量费用上增加的余量（百分比）
+pub const FEE_LIMIT_MARGIN: i64 = 20;
+
+/// 节点按交易大小计算带宽时额外计入的交易结果大小（字节）
+const MAX_RESULT_SIZE_IN_TX: usize = 64;
+/// 签名的长度，r || s || v
+const SIGNATURE_SIZE: usize = 65;
+
+/// 合约调用的费用估算
+///
+/// 带宽按 TRON 的规则计费：质押获得的带宽或免费带宽足够时不收费，否则按整笔交易的大小燃烧 TRX；
+/// 能量先使用账户可用的能量，不足的部分燃烧 TRX。
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CostEstimate {
+    /// 试运行消耗的能量
+    pub energy_used: i64,
+    /// 签名后的交易占用的带宽（字节），按一个签名计算
+    pub bandwidth_used: i64,
+    /// 账户可用的能量
+    pub available_energy: i64,
+    /// 账户可用的免费带宽
+    pub available_free_bandwidth: i64,
+    /// 账户质押获得的可用带宽
+    pub available_staked_bandwidth: i64,
+    /// 每单位能量的价格（sun），链参数 `getEnergyFee`
+    pub energy_fee: i64,
+    /// 每字节带宽的价格（sun），链参数 `getTransactionFee`
+    pub transaction_fee: i64,
+    /// 链参数 `getMaxFeeLimit`，节点不支持时为 `None`
+    pub max_fee_limit: Option<i64>,
+}
+
+impl CostEstimate {
+    /// 可用能量不足时需要燃烧的 TRX（sun）
+    pub fn energy_cost(&self) -> i64 {
+        (self.energy_used - self.available_energy).max(0) * self.energy_fee
+    }
+
+    /// 可用带宽不足时需要燃烧的 TRX（sun）
+    pub fn bandwidth_cost(&self) -> i64 {
+        if self.available_staked_bandwidth >= self.bandwidth_used || self.available_free_bandwidth >= self.bandwidth_used {
+            0
+        } else {
+            self.bandwidth_used * self.transaction_fee
+        }
+    }
+
+    /// 预计燃烧的 TRX 总量（sun）
+    pub fn total_cost(&self) -> i64 {
+        self.energy_cost() + self.bandwidth_cost()
+    }
+
+    /// 建议的手续费上限（sun）
+    ///
+    /// 手续费上限限制的是合约执行消耗的全部能量（包括质押获得的能量）折合的 TRX，
+    /// 因此按 `energy_used` 计算并增加 [`FEE_LIMIT_MARGIN`] 的余量，不超过链上允许的最大值。
+    /// 节点没有返回能量消耗（`energy_used` 为 0）时使用 [`DEFAULT_FEE_LIMIT`]，避免交易因能量不足失败。
+    pub fn fee_limit(&self) -> i64 {
+        let fee_limit = match self.energy_used {
+            0 => DEFAULT_FEE_LIMIT,
+            energy_used => energy_used * self.energy_fee * (100 + FEE_LIMIT_MARGIN) / 100,
+        };
+
+        match self.max_fee_limit {
+            Some(max_fee_limit) => fee_limit.min(max_fee_limit),
+            None => fee_limit,
+        }
+    }
+}
+
+#[async_trait]
+pub trait Estimate {
+    /// 链参数，键为 `getEnergyFee` 这样的参数名
+    async fn chain_parameters(&mut self) -> Result<HashMap<String, i64>>;
+    async fn account_resource(&mut self, address: &Address) -> Result<AccountResourceMessage>;
+
+    /// 试运行合约调用，估算签名者发送该交易的费用
+    async fn estimate(&mut self, contract: &Address, data: Vec<u8>, options: &TxOptions) -> Result<CostEstimate>;
+    /// 与 [`Transfer::contract_transfer`] 相同，未设置手续费上限时使用估算的 [`CostEstimate::fee_limit`]
+    async fn estimated_contract_transfer(&mut self, contract: &Address, data: Vec<u8>, options: &TxOptions) -> Result<BroadcastReceipt>;
+}
+
+#[async_trait]
+impl<'s, S: Signer> Estimate for ServiceAgent<'s, S> {
+    async fn chain_parameters(&mut self) -> Result<HashMap<String, i64>> {
+        let parameters = self
+            .call(EmptyMessage {}, |mut client, request| async move { client.get_chain_parameters(request).await })
+            .await?
+            .into_inner();
+
+        Ok(parameters.chain_parameter.into_iter().map(|parameter| (parameter.key, parameter.value)).collect())
+    }
+
+    async fn account_resource(&mut self, address: &Address) -> Result<AccountResourceMessage> {
+        let account = Account {
+            address: address.into(),
+            ..Default::default()
+        };
+        let resource = self
+            .call(account, |mut client, account| async move { client.get_account_resource(account).await })
+            .await?
+            .into_inner();
+
+        Ok(resource)
+    }
+
+    async fn estimate(&mut self, contract: &Address, data: Vec<u8>, options: &TxOptions) -> Result<CostEstimate> {
+        let options = self.options.merge(options);
+        let request = TriggerSmartContract {
+            owner_address: self.signer.address().into(),
+            contract_address: contract.into(),
+            call_value: options.call_value.unwrap_or_default(),
+            data,
+            call_token_value: options.call_token_value.unwrap_or_default(),
+            token_id: options.token_id.unwrap_or_default(),
+        };
+        let transaction_ext = self
+            .call(request, |mut client, request| async move { client.trigger_constant_contract(request).await })
+            .await?
+            .into_inner();
+        // 试运行回滚时交易也会失败
+        Error::check_constant_result(&transaction_ext)?;
+
+        let parameter = |parameters: &HashMap<String, i64>, key: &str| {
+            parameters.get(key).copied().ok_or_else(|| Error::ChainParameter(key.into()))
+        };
+        let parameters = self.chain_parameters().await?;
+        let owner = *self.signer.address();
+        let resource = self.account_resource(&owner).await?;
+
+        let mut estimate = CostEstimate {
+            energy_used: transaction_ext.energy_used,
+            bandwidth_used: 0,
+            available_energy: (resource.energy_limit - resource.energy_used).max(0),
+            available_free_bandwidth: (resource.free_net_limit - resource.free_net_used).max(0),
+            available_staked_bandwidth: (resource.net_limit - resource.net_used).max(0),
+            energy_fee: parameter(&parameters, "getEnergyFee")?,
+            transaction_fee: parameter(&parameters, "getTransactionFee")?,
+            max_fee_limit: parameters.get("getMaxFeeLimit").copied(),
+        };
+
+        // 按最终发送的交易计算大小，手续费上限的取值会影响编码长度
+        let mut raw_data = transaction_ext.transaction.and_then(|transaction| transaction.raw_data).ok_or(Error::EmptyTransaction)?;
+        let fee_limit = options.fee_limit.unwrap_or_else(|| estimate.fee_limit());
+        options.fee_limit(fee_limit).apply(&mut raw_data)?;
+        let transaction = Transaction {
+            raw_data: Some(raw_data),
+            signature: vec![vec![0; SIGNATURE_SIZE]],
+            ret: vec![],
+        };
+        estimate.bandwidth_used = (transaction.encoded_len() + MAX_RESULT_SIZE_IN_TX) as i64;
+
+        Ok(estimate)
+    }
+
+    async fn estimated_contract_transfer(&mut self, contract: &Address, data: Vec<u8>, options: &TxOptions) -> Result<BroadcastReceipt> {
+        let mut options = options.clone();
+        if self.options.merge(&options).fee_limit.is_none() {
+            let estimate = self.estimate(contract, data.clone(), &options).await?;
+            info!("estimated energy {}, cost {} sun, fee limit {}", estimate.energy_used, estimate.total_cost(), estimate.fee_limit());
+            options.fee_limit = Some(estimate.fee_limit());
+        }
+
+        self.contract_transfer(contract, data, &options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::apis::chain_parameters::ChainParameter;
+    use crate::apis::{transaction, ChainParameters, Return, TransactionExtention};
+    use crate::key::PrivateKey;
+    use crate::services::mock::MockWallet;
+    use crate::services::{Service, ServiceConfig};
+
+    fn estimate() -> CostEstimate {
+        CostEstimate {
+            energy_used: 30_000,
+            bandwidth_used: 345,
+            available_energy: 10_000,
+            available_free_bandwidth: 600,
+            available_staked_bandwidth: 0,
+            energy_fee: 420,
+            transaction_fee: 1000,
+            max_fee_limit: Some(15_000_000_000),
+        }
+    }
+
+    #[test]
+    fn test_cost() {
+        let estimate = estimate();
+        assert_eq!(estimate.energy_cost(), 20_000 * 420);
+        assert_eq!(estimate.bandwidth_cost(), 0);
+        assert_eq!(estimate.fee_limit(), 30_000 * 420 * 120 / 100);
+
+        // 带宽不足时按整笔交易燃烧
+        let estimate = CostEstimate { available_free_bandwidth: 200, available_staked_bandwidth: 300, ..estimate };
+        assert_eq!(estimate.bandwidth_cost(), 345_000);
+        assert_eq!(estimate.total_cost(), 20_000 * 420 + 345_000);
+
+        let estimate = CostEstimate { max_fee_limit: Some(1_000000), ..estimate };
+        assert_eq!(estimate.fee_limit(), 1_000000);
+
+        let estimate = CostEstimate { energy_used: 0, max_fee_limit: None, ..estimate };
+        assert_eq!(estimate.energy_cost(), 0);
+        assert_eq!(estimate.fee_limit(), DEFAULT_FEE_LIMIT);
+    }
+
+    fn transaction_ext(energy_used: i64) -> impl Fn(TriggerSmartContract) -> std::result::Result<TransactionExtention, tonic::Status> {
+        move |_| Ok(TransactionExtention {
+            transaction: Some(Transaction {
+                raw_data: Some(transaction::Raw { timestamp: 1, ..Default::default() }),
+                ..Default::default()
+            }),
+            energy_used,
+            ..Default::default()
+        })
+    }
+
+    async fn wallet(energy_used: i64, broadcasted: Arc<Mutex<Option<Transaction>>>) -> String {
+        MockWallet::new()
+            .unary("TriggerConstantContract", transaction_ext(energy_used))
+            .unary("TriggerContract", transaction_ext(energy_used))
+            .unary("GetChainParameters", |_: EmptyMessage| {
+                let parameter = |key: &str, value| ChainParameter { key: key.into(), value };
+                Ok(ChainParameters {
+                    chain_parameter: vec![parameter("getEnergyFee", 420), parameter("getTransactionFee", 1000)],
+                })
+            })
+            .unary("GetAccountResource", |_: Account| {
+                Ok(AccountResourceMessage { free_net_limit: 600, free_net_used: 500, energy_limit: 4000, ..Default::default() })
+            })
+            .unary("BroadcastTransaction", move |transaction: Transaction| {
+                *broadcasted.lock().unwrap() = Some(transaction);
+                Ok(Return { result: true, ..Default::default() })
+            })
+            .serve()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_estimated_contract_transfer() {
+        let broadcasted = Arc::new(Mutex::new(None));
+        let endpoint = wallet(14_000, broadcasted.clone()).await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate());
+        let contract = Address::from_evm([0x11; 20]);
+
+        let estimate = agent.estimate(&contract, vec![], &TxOptions::default()).await.unwrap();
+        assert_eq!(estimate.energy_cost(), 10_000 * 420);
+        assert!(estimate.bandwidth_used > 100);
+        assert_eq!(estimate.bandwidth_cost(), estimate.bandwidth_used * 1000);
+        assert_eq!(estimate.max_fee_limit, None);
+
+        agent.estimated_contract_transfer(&contract, vec![], &TxOptions::default()).await.unwrap();
+        let transaction = broadcasted.lock().unwrap().take().unwrap();
+        assert_eq!(transaction.raw_data.unwrap().fee_limit, estimate.fee_limit());
+
+        // 设置了手续费上限时不估算
+        agent.estimated_contract_transfer(&contract, vec![], &TxOptions::new().fee_limit(1_000000)).await.unwrap();
+        let transaction = broadcasted.lock().unwrap().take().unwrap();
+        assert_eq!(transaction.raw_data.unwrap().fee_limit, 1_000000);
+    }
+
+    #[tokio::test]
+    async fn test_estimated_without_energy_used() {
+        let broadcasted = Arc::new(Mutex::new(None));
+        let endpoint = wallet(0, broadcasted.clone()).await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate());
+        let contract = Address::from_evm([0x11; 20]);
+
+        // 节点没有返回能量消耗时不能把手续费上限设为 0
+        agent.estimated_contract_transfer(&contract, vec![], &TxOptions::default()).await.unwrap();
+        let transaction = broadcasted.lock().unwrap().take().unwrap();
+        assert_eq!(transaction.raw_data.unwrap().fee_limit, DEFAULT_FEE_LIMIT);
+    }
+}
@@ -0,0 +1,120 @@
+//! 测试用的 `protocol.Wallet` 模拟节点
+//!
+//! 只实现通过 [`MockWallet::unary`] 注册的方法，其余方法返回 `Unimplemented`。
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::future::{self, BoxFuture, Ready};
+use hyper::Body;
+use prost::Message;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::body::BoxBody;
+use tonic::codec::ProstCodec;
+use tonic::server::{Grpc, UnaryService};
+use tonic::transport::{NamedService, Server};
+use tonic::{Request, Response, Status};
+
+type Route = Arc<dyn Fn(http::Request<Body>) -> BoxFuture<'static, http::Response<BoxBody>> + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub struct MockWallet {
+    routes: HashMap<&'static str, Route>,
+}
+
+struct Handler<F>(Arc<F>);
+
+impl<Req, Res, F> UnaryService<Req> for Handler<F>
+    where F: Fn(Req) -> Result<Res, Status>
+{
+    type Response = Res;
+    type Future = Ready<Result<Response<Res>, Status>>;
+
+    fn call(&mut self, request: Request<Req>) -> Self::Future {
+        future::ready((self.0)(request.into_inner()).map(Response::new))
+    }
+}
+
+impl MockWallet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册一个一元调用，`method` 为 proto 中的方法名，如 `GetNowBlock2` 或 `WalletSolidity/GetNowBlock2`
+    pub fn unary<Req, Res, F>(mut self, method: &'static str, handler: F) -> Self
+        where Req: Message + Default + Send + Sync + 'static,
+              Res: Message + Send + Sync + 'static,
+              F: Fn(Req) -> Result<Res, Status> + Send + Sync + 'static
+    {
+        let handler = Arc::new(handler);
+        self.routes.insert(method, Arc::new(move |request| {
+            let handler = Handler(handler.clone());
+            Box::pin(async move {
+                Grpc::new(ProstCodec::<Res, Req>::default()).unary(handler, request).await
+            })
+        }));
+        self
+    }
+
+    /// 在随机端口上启动服务，返回可用于 [`ServiceConfig`](crate::services::ServiceConfig) 的地址
+    pub async fn serve(self) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(
+            Server::builder()
+                .add_service(self)
+                .serve_with_incoming(TcpListenerStream::new(listener))
+        );
+
+        format!("http://{}", addr)
+    }
+}
+
+/// 获取一个当前没有服务监听的地址，用于模拟宕机的节点
+pub async fn dead_endpoint() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+    drop(listener);
+
+    format!("http://{}", addr)
+}
+
+impl tower::Service<http::Request<Body>> for MockWallet {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        // `protocol.WalletSolidity` 的方法以 `WalletSolidity/` 为前缀注册
+        let route = request.uri().path()
+            .strip_prefix("/protocol.")
+            .map(|path| path.strip_prefix("Wallet/").unwrap_or(path))
+            .and_then(|method| self.routes.get(method))
+            .cloned();
+
+        Box::pin(async move {
+            Ok(match route {
+                Some(route) => route(request).await,
+                None => http::Response::builder()
+                    .status(200)
+                    .header("grpc-status", "12")
+                    .header("content-type", "application/grpc")
+                    .body(BoxBody::empty())
+                    .unwrap(),
+            })
+        })
+    }
+}
+
+impl NamedService for MockWallet {
+    const NAME: &'static str = "protocol.Wallet";
+}
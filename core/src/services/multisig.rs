@@ -0,0 +1,210 @@
+use std::convert::TryFrom;
+
+use crate::apis::transaction_sign_weight::result::ResponseCode;
+use crate::apis::{Permission, Transaction};
+use crate::error::Error;
+use crate::key::Address;
+use crate::services::ServiceAgent;
+use crate::signer::{sign_transaction, Signer};
+use crate::transaction::{BroadcastReceipt, SignedTransaction};
+use crate::utils::transaction_id;
+use crate::Result;
+
+/// 交易当前的签名权重
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignWeight {
+    /// 交易使用的账户权限，由交易中合约的 `permission_id` 决定
+    pub permission: Permission,
+    /// 已签名的地址
+    pub approved: Vec<Address>,
+    /// 已签名地址的权重之和
+    pub current_weight: i64,
+}
+
+impl SignWeight {
+    pub fn threshold(&self) -> i64 {
+        self.permission.threshold
+    }
+
+    /// 签名权重是否已达到权限阈值
+    pub fn is_enough(&self) -> bool {
+        self.current_weight >= self.threshold()
+    }
+
+    pub fn is_approved(&self, address: &Address) -> bool {
+        self.approved.contains(address)
+    }
+}
+
+/// 使用账户权限的多签交易
+///
+/// 通过 [`TxOptions::permission_id`](crate::services::TxOptions::permission_id) 指定交易使用的权限，
+/// 由多个 [`Signer`] 依次签名，签名权重达到权限阈值后广播。
+#[async_trait]
+pub trait MultiSig {
+    /// 由节点计算交易当前的签名权重
+    async fn sign_weight(&mut self, transaction: &Transaction) -> Result<SignWeight>;
+
+    /// 依次追加签名，权重达到阈值后不再签名
+    ///
+    /// 已经签过名的签名者会被跳过。签名者不足时同样返回签名后的交易，可以交给其他签名者继续签名。
+    async fn multi_sign(&mut self, transaction: Transaction, signers: &[&dyn Signer]) -> Result<SignedTransaction>;
+
+    /// 确认签名权重达到阈值后广播，未达到时返回 [`Error::InsufficientWeight`]
+    async fn broadcast_multi_signed(&mut self, signed: SignedTransaction) -> Result<BroadcastReceipt>;
+}
+
+#[async_trait]
+impl<'s, S: Signer> MultiSig for ServiceAgent<'s, S> {
+    async fn sign_weight(&mut self, transaction: &Transaction) -> Result<SignWeight> {
+        let weight = self
+            .call(transaction.clone(), |mut client, transaction| async move { client.get_transaction_sign_weight(transaction).await })
+            .await?
+            .into_inner();
+
+        let result = weight.result.unwrap_or_default();
+        match ResponseCode::from_i32(result.code) {
+            Some(ResponseCode::EnoughPermission) | Some(ResponseCode::NotEnoughPermission) => {}
+            _ => return Err(Error::Permission(result.message)),
+        }
+
+        Ok(SignWeight {
+            permission: weight.permission.unwrap_or_default(),
+            approved: weight.approved_list.iter()
+                .filter_map(|address| Address::try_from(&address[..]).ok())
+                .collect(),
+            current_weight: weight.current_weight,
+        })
+    }
+
+    async fn multi_sign(&mut self, transaction: Transaction, signers: &[&dyn Signer]) -> Result<SignedTransaction> {
+        let txid = transaction_id(transaction.raw_data.as_ref().ok_or(Error::EmptyTransaction)?);
+        let mut transaction = transaction;
+        let mut weight = match transaction.signature.is_empty() {
+            true => None,
+            false => Some(self.sign_weight(&transaction).await?),
+        };
+
+        for signer in signers {
+            if let Some(weight) = &weight {
+                if weight.is_enough() {
+                    break;
+                }
+                if weight.is_approved(signer.address()) {
+                    continue;
+                }
+            }
+
+            transaction = sign_transaction(*signer, transaction).await?.transaction;
+            let current = self.sign_weight(&transaction).await?;
+            info!(
+                "transaction {} signed by {}, weight {}/{}",
+                hex::encode(txid), signer.address().to_base58(), current.current_weight, current.threshold()
+            );
+            weight = Some(current);
+        }
+
+        Ok(SignedTransaction { txid, transaction })
+    }
+
+    async fn broadcast_multi_signed(&mut self, signed: SignedTransaction) -> Result<BroadcastReceipt> {
+        let weight = self.sign_weight(&signed.transaction).await?;
+        if !weight.is_enough() {
+            return Err(Error::InsufficientWeight {
+                current: weight.current_weight,
+                threshold: weight.threshold(),
+            });
+        }
+
+        self.broadcast_transaction(signed).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::apis::{transaction, Key, Return, TransactionSignWeight, transaction_sign_weight};
+    use crate::key::PrivateKey;
+    use crate::services::mock::MockWallet;
+    use crate::services::{Service, ServiceConfig, TxOptions};
+
+    /// 2-of-3 权限，每个签名的权重为 1
+    fn sign_weight(transaction: Transaction) -> std::result::Result<TransactionSignWeight, tonic::Status> {
+        let current_weight = transaction.signature.len() as i64;
+        let code = match current_weight >= 2 {
+            true => transaction_sign_weight::result::ResponseCode::EnoughPermission,
+            false => transaction_sign_weight::result::ResponseCode::NotEnoughPermission,
+        };
+
+        Ok(TransactionSignWeight {
+            permission: Some(Permission {
+                id: 2,
+                threshold: 2,
+                keys: vec![Key::default(); 3],
+                ..Default::default()
+            }),
+            current_weight,
+            result: Some(transaction_sign_weight::Result { code: code as i32, message: String::new() }),
+            ..Default::default()
+        })
+    }
+
+    fn unsigned_transaction() -> Transaction {
+        let mut raw_data = transaction::Raw {
+            contract: vec![transaction::Contract::default()],
+            timestamp: 1,
+            ..Default::default()
+        };
+        TxOptions::new().permission_id(2).apply(&mut raw_data).unwrap();
+
+        Transaction { raw_data: Some(raw_data), ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_multi_sign() {
+        let broadcasted = Arc::new(Mutex::new(None));
+        let broadcast = broadcasted.clone();
+        let endpoint = MockWallet::new()
+            .unary("GetTransactionSignWeight", sign_weight)
+            .unary("BroadcastTransaction", move |transaction: Transaction| {
+                *broadcast.lock().unwrap() = Some(transaction);
+                Ok(Return { result: true, ..Default::default() })
+            })
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let keys = [PrivateKey::generate(), PrivateKey::generate(), PrivateKey::generate()];
+        let mut agent = service.agent(PrivateKey::generate());
+
+        let signers: Vec<&dyn Signer> = keys.iter().map(|key| key as &dyn Signer).collect();
+        let signed = agent.multi_sign(unsigned_transaction(), &signers).await.unwrap();
+        assert_eq!(signed.transaction.signature.len(), 2);
+        assert_eq!(signed.transaction.signature[1], keys[1].sign_hash(&signed.txid).await.unwrap());
+
+        let receipt = agent.broadcast_multi_signed(signed).await.unwrap();
+        let transaction = broadcasted.lock().unwrap().take().unwrap();
+        assert_eq!(transaction.raw_data.unwrap().contract[0].permission_id, 2);
+        assert_eq!(receipt.signed_transaction.signature.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_insufficient_weight() {
+        let endpoint = MockWallet::new()
+            .unary("GetTransactionSignWeight", sign_weight)
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let key = PrivateKey::generate();
+        let mut agent = service.agent(PrivateKey::generate());
+
+        let signed = agent.multi_sign(unsigned_transaction(), &[&key]).await.unwrap();
+        assert!(matches!(
+            agent.broadcast_multi_signed(signed).await,
+            Err(Error::InsufficientWeight { current: 1, threshold: 2 })
+        ));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::apis::transaction::contract::ContractType;
+use crate::apis::transaction::Raw;
+use crate::error::Error;
+use crate::Result;
+
+/// 合约调用未指定手续费上限时使用的默认值（10 TRX）
+pub const DEFAULT_FEE_LIMIT: i64 = 10_000000;
+
+/// 交易选项
+///
+/// 未设置的项使用 [`ServiceAgent`](crate::services::ServiceAgent) 上的默认选项，
+/// 默认选项也未设置时保持节点创建交易时的取值。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct TxOptions {
+    /// 手续费上限（sun），仅对合约调用有意义，合约调用未设置时为 [`DEFAULT_FEE_LIMIT`]
+    pub fee_limit: Option<i64>,
+    /// 交易备注，写入 `raw_data.data`
+    pub memo: Option<Vec<u8>>,
+    /// 交易过期时间（毫秒时间戳），覆盖节点设置的过期时间
+    pub expiration: Option<i64>,
+    /// 签名使用的账户权限 ID，多签时使用
+    pub permission_id: Option<i32>,
+    /// 合约调用时转入合约的 TRX 数量（sun），只能用于合约调用
+    pub call_value: Option<i64>,
+    /// 合约调用时转入合约的 TRC10 代币数量，只能用于合约调用
+    pub call_token_value: Option<i64>,
+    /// 合约调用时转入合约的 TRC10 代币 ID，只能用于合约调用
+    pub token_id: Option<i64>,
+}
+
+impl TxOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fee_limit(mut self, fee_limit: i64) -> Self {
+        self.fee_limit = Some(fee_limit);
+        self
+    }
+
+    pub fn memo<T: Into<Vec<u8>>>(mut self, memo: T) -> Self {
+        self.memo = Some(memo.into());
+        self
+    }
+
+    pub fn expiration(mut self, expiration: i64) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    pub fn permission_id(mut self, permission_id: i32) -> Self {
+        self.permission_id = Some(permission_id);
+        self
+    }
+
+    pub fn call_value(mut self, call_value: i64) -> Self {
+        self.call_value = Some(call_value);
+        self
+    }
+
+    pub fn call_token(mut self, token_id: i64, call_token_value: i64) -> Self {
+        self.token_id = Some(token_id);
+        self.call_token_value = Some(call_token_value);
+        self
+    }
+
+    /// 以 `self` 为默认值合并 `overrides` 中设置的项
+    pub fn merge(&self, overrides: &TxOptions) -> TxOptions {
+        TxOptions {
+            fee_limit: overrides.fee_limit.or(self.fee_limit),
+            memo: overrides.memo.clone().or_else(|| self.memo.clone()),
+            expiration: overrides.expiration.or(self.expiration),
+            permission_id: overrides.permission_id.or(self.permission_id),
+            call_value: overrides.call_value.or(self.call_value),
+            call_token_value: overrides.call_token_value.or(self.call_token_value),
+            token_id: overrides.token_id.or(self.token_id),
+        }
+    }
+
+    /// 将选项写入节点创建的交易
+    ///
+    /// `call_value`、`call_token_value` 和 `token_id` 在创建合约调用时写入，
+    /// 用于其他交易时返回 [`Error::InvalidOptions`]，避免被静默忽略。
+    pub fn apply(&self, raw_data: &mut Raw) -> Result<()> {
+        let is_trigger = !raw_data.contract.is_empty() && raw_data.contract.iter()
+            .all(|contract| contract.r#type == ContractType::TriggerSmartContract as i32);
+        if !is_trigger && (self.call_value.is_some() || self.call_token_value.is_some() || self.token_id.is_some()) {
+            return Err(Error::InvalidOptions("call_value, call_token_value and token_id only apply to contract calls".into()));
+        }
+
+        let is_contract_call = raw_data.contract.iter().any(|contract| {
+            contract.r#type == ContractType::TriggerSmartContract as i32
+                || contract.r#type == ContractType::CreateSmartContract as i32
+        });
+
+        match self.fee_limit {
+            Some(fee_limit) => raw_data.fee_limit = fee_limit,
+            None if is_contract_call && raw_data.fee_limit == 0 => raw_data.fee_limit = DEFAULT_FEE_LIMIT,
+            None => {}
+        }
+        if let Some(memo) = &self.memo {
+            raw_data.data = memo.clone();
+        }
+        if let Some(expiration) = self.expiration {
+            raw_data.expiration = expiration;
+        }
+        if let Some(permission_id) = self.permission_id {
+            for contract in raw_data.contract.iter_mut() {
+                contract.permission_id = permission_id;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::transaction::Contract;
+
+    fn raw_data(r#type: ContractType) -> Raw {
+        Raw {
+            contract: vec![Contract { r#type: r#type as i32, ..Default::default() }],
+            expiration: 60_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let defaults = TxOptions::new().fee_limit(20_000000).memo("default");
+        let options = defaults.merge(&TxOptions::new().memo("payout #1").permission_id(2));
+
+        assert_eq!(options, TxOptions {
+            fee_limit: Some(20_000000),
+            memo: Some(b"payout #1".to_vec()),
+            permission_id: Some(2),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut raw = raw_data(ContractType::TransferContract);
+        TxOptions::new().apply(&mut raw).unwrap();
+        assert_eq!(raw, raw_data(ContractType::TransferContract));
+
+        let mut raw = raw_data(ContractType::TriggerSmartContract);
+        TxOptions::new().apply(&mut raw).unwrap();
+        assert_eq!(raw.fee_limit, DEFAULT_FEE_LIMIT);
+
+        let mut raw = raw_data(ContractType::TriggerSmartContract);
+        TxOptions::new().fee_limit(50_000000).memo("memo").expiration(120_000).permission_id(2).apply(&mut raw).unwrap();
+        assert_eq!(raw.fee_limit, 50_000000);
+        assert_eq!(raw.data, b"memo");
+        assert_eq!(raw.expiration, 120_000);
+        assert_eq!(raw.contract[0].permission_id, 2);
+    }
+
+    #[test]
+    fn test_contract_only_options() {
+        let mut raw = raw_data(ContractType::TriggerSmartContract);
+        assert!(TxOptions::new().call_value(1_000000).call_token(1000001, 5).apply(&mut raw).is_ok());
+
+        // TRX 转账、质押等交易不能设置合约调用的选项
+        for options in [TxOptions::new().call_value(1_000000), TxOptions::new().call_token(1000001, 5)] {
+            for r#type in [ContractType::TransferContract, ContractType::FreezeBalanceContract] {
+                let mut raw = raw_data(r#type);
+                assert!(matches!(options.apply(&mut raw), Err(Error::InvalidOptions(_))));
+            }
+        }
+    }
+}
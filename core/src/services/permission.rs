@@ -0,0 +1,325 @@
+use std::collections::HashSet;
+
+use crate::apis::permission::PermissionType;
+use crate::apis::transaction::contract::ContractType;
+use crate::apis::{Account, AccountPermissionUpdateContract, Key, Permission, Transaction};
+use crate::error::Error;
+use crate::key::Address;
+use crate::Result;
+use crate::services::{ServiceAgent, TxOptions};
+use crate::signer::Signer;
+use crate::transaction::BroadcastReceipt;
+
+/// 每个权限最多的密钥数（链参数 `getTotalSignNum` 的默认值）
+pub const MAX_PERMISSION_KEYS: usize = 5;
+/// 最多的 active 权限数
+pub const MAX_ACTIVE_PERMISSIONS: usize = 8;
+/// 未修改过权限的账户默认 active 权限的操作位图
+pub const DEFAULT_ACTIVE_OPERATIONS: [u8; 32] = [
+    0x7f, 0xff, 0x1f, 0xc0, 0x03, 0x3e, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// 账户的 owner / witness / active 权限
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountPermissions {
+    pub owner: Permission,
+    /// 仅超级代表账户有 witness 权限
+    pub witness: Option<Permission>,
+    pub actives: Vec<Permission>,
+}
+
+impl AccountPermissions {
+    /// 从 `get_account` 返回的账户中读取权限，未修改过权限的账户使用节点的默认权限
+    pub fn from_account(account: &Account) -> Self {
+        let default_key = || vec![Key { address: account.address.clone(), weight: 1 }];
+
+        let owner = account.owner_permission.clone().unwrap_or_else(|| Permission {
+            r#type: PermissionType::Owner as i32,
+            permission_name: "owner".into(),
+            threshold: 1,
+            keys: default_key(),
+            ..Default::default()
+        });
+        let actives = match account.active_permission.is_empty() {
+            true => vec![Permission {
+                r#type: PermissionType::Active as i32,
+                id: 2,
+                permission_name: "active".into(),
+                threshold: 1,
+                operations: DEFAULT_ACTIVE_OPERATIONS.to_vec(),
+                keys: default_key(),
+                ..Default::default()
+            }],
+            false => account.active_permission.clone(),
+        };
+
+        Self {
+            owner,
+            witness: account.witness_permission.clone(),
+            actives,
+        }
+    }
+
+    /// 按 ID 查找 active 权限
+    pub fn active_mut(&mut self, id: i32) -> Option<&mut Permission> {
+        self.actives.iter_mut().find(|permission| permission.id == id)
+    }
+
+    /// 在本地按节点的规则检查权限，避免提交后账户无法再签名
+    pub fn validate(&self) -> Result<()> {
+        validate_permission(&self.owner, PermissionType::Owner)?;
+        if let Some(witness) = &self.witness {
+            validate_permission(witness, PermissionType::Witness)?;
+            if witness.keys.len() != 1 {
+                return Err(Error::Permission("witness permission must have exactly one key".into()));
+            }
+        }
+
+        if self.actives.is_empty() || self.actives.len() > MAX_ACTIVE_PERMISSIONS {
+            return Err(Error::Permission(format!("active permission count must be between 1 and {}", MAX_ACTIVE_PERMISSIONS)));
+        }
+        for active in &self.actives {
+            validate_permission(active, PermissionType::Active)?;
+            if active.operations.len() != 32 || active.operations.iter().all(|byte| *byte == 0) {
+                return Err(Error::Permission(format!("operations of active permission {} is invalid", active.permission_name)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_permission(permission: &Permission, r#type: PermissionType) -> Result<()> {
+    let name = &permission.permission_name;
+    if permission.r#type != r#type as i32 {
+        return Err(Error::Permission(format!("permission {} has wrong type {}", name, permission.r#type)));
+    }
+    if permission.keys.is_empty() || permission.keys.len() > MAX_PERMISSION_KEYS {
+        return Err(Error::Permission(format!("key count of permission {} must be between 1 and {}", name, MAX_PERMISSION_KEYS)));
+    }
+    if permission.threshold <= 0 {
+        return Err(Error::Permission(format!("threshold of permission {} must be positive", name)));
+    }
+
+    let mut addresses = HashSet::new();
+    let mut total_weight = 0i64;
+    for key in &permission.keys {
+        if key.address.len() != 21 {
+            return Err(Error::Permission(format!("permission {} has invalid key address {}", name, hex::encode(&key.address))));
+        }
+        if !addresses.insert(&key.address) {
+            return Err(Error::Permission(format!("permission {} has duplicated key {}", name, hex::encode(&key.address))));
+        }
+        if key.weight <= 0 {
+            return Err(Error::Permission(format!("key weight of permission {} must be positive", name)));
+        }
+        total_weight = total_weight.saturating_add(key.weight);
+    }
+
+    // 权重之和达不到阈值时该权限再也无法签名
+    if total_weight < permission.threshold {
+        return Err(Error::Permission(format!(
+            "threshold {} of permission {} is unreachable, total weight is {}",
+            permission.threshold, name, total_weight
+        )));
+    }
+
+    Ok(())
+}
+
+/// 修改单个权限
+pub trait PermissionExt {
+    /// 添加密钥，密钥已存在时修改其权重
+    fn set_key(&mut self, address: &Address, weight: i64) -> &mut Self;
+    /// 移除密钥，返回密钥是否存在
+    fn remove_key(&mut self, address: &Address) -> bool;
+    fn set_threshold(&mut self, threshold: i64) -> &mut Self;
+    /// 设置 active 权限允许的合约类型
+    fn set_operations(&mut self, operations: &[ContractType]) -> &mut Self;
+    /// active 权限允许的合约类型
+    fn operations(&self) -> Vec<ContractType>;
+}
+
+impl PermissionExt for Permission {
+    fn set_key(&mut self, address: &Address, weight: i64) -> &mut Self {
+        match self.keys.iter_mut().find(|key| key.address[..] == address[..]) {
+            Some(key) => key.weight = weight,
+            None => self.keys.push(Key { address: address.into(), weight }),
+        }
+        self
+    }
+
+    fn remove_key(&mut self, address: &Address) -> bool {
+        let len = self.keys.len();
+        self.keys.retain(|key| key.address[..] != address[..]);
+        self.keys.len() != len
+    }
+
+    fn set_threshold(&mut self, threshold: i64) -> &mut Self {
+        self.threshold = threshold;
+        self
+    }
+
+    fn set_operations(&mut self, operations: &[ContractType]) -> &mut Self {
+        let mut bitmask = vec![0u8; 32];
+        for operation in operations {
+            let bit = *operation as usize;
+            bitmask[bit / 8] |= 1 << (bit % 8);
+        }
+        self.operations = bitmask;
+        self
+    }
+
+    fn operations(&self) -> Vec<ContractType> {
+        (0..self.operations.len() * 8)
+            .filter(|bit| self.operations[bit / 8] & (1 << (bit % 8)) != 0)
+            .filter_map(|bit| ContractType::from_i32(bit as i32))
+            .collect()
+    }
+}
+
+#[async_trait]
+pub trait Permissions {
+    /// 读取签名者账户当前的权限
+    async fn account_permissions(&mut self) -> Result<AccountPermissions>;
+    /// 在本地检查权限后由节点创建权限更新交易，返回待签名的交易
+    async fn build_account_permission_update(&mut self, permissions: &AccountPermissions, options: &TxOptions) -> Result<Transaction>;
+    async fn account_permission_update(&mut self, permissions: &AccountPermissions, options: &TxOptions) -> Result<BroadcastReceipt>;
+}
+
+#[async_trait]
+impl<'s, S: Signer> Permissions for ServiceAgent<'s, S> {
+    async fn account_permissions(&mut self) -> Result<AccountPermissions> {
+        let account = Account {
+            address: self.signer.address().into(),
+            ..Default::default()
+        };
+        let account = self
+            .call(account, |mut client, account| async move { client.get_account(account).await })
+            .await?
+            .into_inner();
+
+        Ok(AccountPermissions::from_account(&account))
+    }
+
+    async fn build_account_permission_update(&mut self, permissions: &AccountPermissions, options: &TxOptions) -> Result<Transaction> {
+        permissions.validate()?;
+
+        let contract = AccountPermissionUpdateContract {
+            owner_address: self.signer.address().into(),
+            owner: Some(permissions.owner.clone()),
+            witness: permissions.witness.clone(),
+            actives: permissions.actives.clone(),
+        };
+        let trx_ext = self
+            .call(contract, |mut client, contract| async move { client.account_permission_update(contract).await })
+            .await?
+            .into_inner();
+
+        self.build(trx_ext, options)
+    }
+
+    async fn account_permission_update(&mut self, permissions: &AccountPermissions, options: &TxOptions) -> Result<BroadcastReceipt> {
+        let transaction = self.build_account_permission_update(permissions, options).await?;
+        let signed = self.sign(transaction).await?;
+
+        self.broadcast_transaction(signed).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::apis::{transaction, Return, TransactionExtention};
+    use crate::key::PrivateKey;
+    use crate::services::mock::MockWallet;
+    use crate::services::{Service, ServiceConfig};
+
+    #[test]
+    fn test_operations() {
+        let mut permission = Permission::default();
+        permission.set_operations(&[ContractType::TransferContract, ContractType::TriggerSmartContract]);
+
+        assert_eq!(hex::encode(&permission.operations[..4]), "02000080");
+        assert_eq!(permission.operations(), vec![ContractType::TransferContract, ContractType::TriggerSmartContract]);
+
+        permission.operations = DEFAULT_ACTIVE_OPERATIONS.to_vec();
+        assert!(permission.operations().contains(&ContractType::TriggerSmartContract));
+        // 修改权限只能使用 owner 权限
+        assert!(!permission.operations().contains(&ContractType::AccountPermissionUpdateContract));
+    }
+
+    #[test]
+    fn test_validate() {
+        let owner = PrivateKey::generate();
+        let mut permissions = AccountPermissions::from_account(&Account {
+            address: owner.address().into(),
+            ..Default::default()
+        });
+        assert!(permissions.validate().is_ok());
+
+        let keys = [PrivateKey::generate(), PrivateKey::generate()];
+        permissions.owner.set_key(keys[0].address(), 1).set_key(keys[1].address(), 1).set_threshold(2);
+        assert!(permissions.validate().is_ok());
+
+        // 移除密钥后阈值无法达到
+        assert!(permissions.owner.remove_key(owner.address()));
+        assert!(permissions.owner.remove_key(keys[0].address()));
+        assert!(matches!(permissions.validate(), Err(Error::Permission(_))));
+
+        permissions.owner.set_key(keys[0].address(), 1);
+        permissions.active_mut(2).unwrap().set_operations(&[]);
+        assert!(permissions.validate().is_err());
+
+        permissions.active_mut(2).unwrap().set_operations(&[ContractType::TransferContract]);
+        assert!(permissions.validate().is_ok());
+
+        for _ in 0..MAX_PERMISSION_KEYS {
+            permissions.owner.set_key(PrivateKey::generate().address(), 1);
+        }
+        assert!(permissions.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_account_permission_update() {
+        let submitted = Arc::new(Mutex::new(None));
+        let captured = submitted.clone();
+        let endpoint = MockWallet::new()
+            .unary("GetAccount", |account: Account| Ok(account))
+            .unary("AccountPermissionUpdate", move |contract: AccountPermissionUpdateContract| {
+                *captured.lock().unwrap() = Some(contract);
+                Ok(TransactionExtention {
+                    transaction: Some(Transaction {
+                        raw_data: Some(transaction::Raw { timestamp: 1, ..Default::default() }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+            })
+            .unary("BroadcastTransaction", |_: Transaction| Ok(Return { result: true, ..Default::default() }))
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate());
+
+        let mut permissions = agent.account_permissions().await.unwrap();
+        assert_eq!(permissions.owner.keys[0].address, Vec::from(agent.signer().address()));
+
+        // 阈值无法达到时不提交
+        permissions.owner.set_threshold(3);
+        assert!(agent.account_permission_update(&permissions, &TxOptions::default()).await.is_err());
+        assert!(submitted.lock().unwrap().is_none());
+
+        let cosigner = PrivateKey::generate();
+        permissions.owner.set_key(cosigner.address(), 2);
+        agent.account_permission_update(&permissions, &TxOptions::default()).await.unwrap();
+
+        let contract = submitted.lock().unwrap().take().unwrap();
+        assert_eq!(contract.owner.unwrap().keys.len(), 2);
+        assert_eq!(contract.actives.len(), 1);
+    }
+}
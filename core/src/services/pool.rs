@@ -0,0 +1,506 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use tokio::task::JoinHandle;
+use tonic::transport::Channel;
+use tonic::{Code, Response, Status};
+
+use crate::apis::wallet_client::WalletClient;
+use crate::apis::wallet_solidity_client::WalletSolidityClient;
+use crate::apis::{BlockExtention, EmptyMessage};
+use crate::error::Error;
+use crate::Result;
+use crate::services::{PoolConfig, Routing, ServiceConfig};
+
+/// 延迟的指数移动平均中新样本所占的权重
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+/// 多节点连接池
+///
+/// 每次调用按照 [`Routing`] 选择一个可用节点，遇到传输层错误时将该节点标记为不可用并依次尝试其余节点。
+/// 后台健康检查通过 `get_now_block2` 获取各节点的最新区块高度，落后最高节点超过 `max_block_lag`
+/// 的节点会被剔除，直到追上为止。
+///
+/// `WalletSolidity` 服务由 solidity 节点单独提供，这些节点有各自的连接，健康检查时只在 solidity 节点之间比较区块高度。
+pub struct NodePool {
+    nodes: Vec<Node>,
+    solidity_nodes: Vec<Node>,
+    routing: Routing,
+    max_block_lag: i64,
+    health_check_interval: Duration,
+    cursor: AtomicUsize,
+}
+
+struct Node {
+    endpoint: String,
+    channel: Channel,
+    state: Mutex<NodeState>,
+}
+
+#[derive(Default)]
+struct NodeState {
+    healthy: bool,
+    ejected: bool,
+    latency: Option<Duration>,
+    head_block: Option<i64>,
+}
+
+/// 节点状态快照
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeStatus {
+    pub endpoint: String,
+    /// 最近一次请求或健康检查是否成功
+    pub healthy: bool,
+    /// 是否因区块高度落后而被剔除
+    pub ejected: bool,
+    /// 平均延迟
+    pub latency: Option<Duration>,
+    /// 最近一次健康检查获取的区块高度
+    pub head_block: Option<i64>,
+}
+
+impl NodeState {
+    fn available(&self) -> bool {
+        self.healthy && !self.ejected
+    }
+
+    fn record_latency(&mut self, sample: Duration) {
+        self.latency = Some(match self.latency {
+            Some(latency) => latency.mul_f64(1.0 - LATENCY_SMOOTHING) + sample.mul_f64(LATENCY_SMOOTHING),
+            None => sample,
+        });
+    }
+}
+
+impl Node {
+    fn state(&self) -> std::sync::MutexGuard<'_, NodeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 判断错误是否由传输层（连接失败、超时等）引起，而非节点正常返回的错误
+pub(crate) fn is_transport_error(status: &Status) -> bool {
+    match status.code() {
+        Code::Unavailable => true,
+        Code::Unknown => status.message().contains("transport error"),
+        Code::Cancelled => status.message().contains("Timeout expired"),
+        _ => false,
+    }
+}
+
+fn block_number(block: &BlockExtention) -> i64 {
+    block.block_header.as_ref()
+        .and_then(|header| header.raw_data.as_ref())
+        .map(|raw| raw.number)
+        .unwrap_or_default()
+}
+
+impl NodePool {
+    /// 连接所有节点
+    ///
+    /// 连接失败的节点会以延迟连接的方式加入连接池并标记为不可用，全节点或 solidity 节点全部连接失败时返回第一个错误。
+    pub async fn connect(config: &PoolConfig) -> Result<Self> {
+        if config.endpoints.is_empty() {
+            return Err(Error::InvalidConfig("no endpoint configured".into()));
+        }
+
+        Ok(Self {
+            nodes: Self::connect_nodes(&config.endpoints).await?,
+            solidity_nodes: Self::connect_nodes(&config.solidity_endpoints).await?,
+            routing: config.routing,
+            max_block_lag: config.max_block_lag,
+            health_check_interval: Duration::from_millis(config.health_check_interval),
+            cursor: AtomicUsize::new(0),
+        })
+    }
+
+    async fn connect_nodes(endpoints: &[ServiceConfig]) -> Result<Vec<Node>> {
+        let channels = join_all(endpoints.iter().map(ServiceConfig::connect)).await;
+
+        let mut nodes = Vec::with_capacity(channels.len());
+        let mut first_error = None;
+        for (endpoint, channel) in endpoints.iter().zip(channels) {
+            let (channel, healthy) = match channel {
+                Ok(channel) => (channel, true),
+                Err(e) => {
+                    warn!("Connect to block chain endpoint {} failed: {}", endpoint.endpoint, e);
+                    first_error.get_or_insert(e);
+                    (endpoint.to_endpoint()?.connect_lazy()?, false)
+                }
+            };
+
+            nodes.push(Node {
+                endpoint: endpoint.endpoint.clone(),
+                channel,
+                state: Mutex::new(NodeState { healthy, ..Default::default() }),
+            });
+        }
+
+        if let Some(e) = first_error {
+            if nodes.iter().all(|node| !node.state().healthy) {
+                return Err(e);
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    pub fn status(&self) -> Vec<NodeStatus> {
+        Self::status_of(&self.nodes)
+    }
+
+    /// solidity 节点的状态
+    pub fn solidity_status(&self) -> Vec<NodeStatus> {
+        Self::status_of(&self.solidity_nodes)
+    }
+
+    fn status_of(nodes: &[Node]) -> Vec<NodeStatus> {
+        nodes.iter()
+            .map(|node| {
+                let state = node.state();
+                NodeStatus {
+                    endpoint: node.endpoint.clone(),
+                    healthy: state.healthy,
+                    ejected: state.ejected,
+                    latency: state.latency,
+                    head_block: state.head_block,
+                }
+            })
+            .collect()
+    }
+
+    /// 按路由策略排列的候选节点，不可用的节点排在最后作为兜底
+    fn candidates<'a>(&self, nodes: &'a [Node]) -> Vec<&'a Node> {
+        let (mut available, unavailable): (Vec<&Node>, Vec<&Node>) = nodes.iter()
+            .partition(|node| node.state().available());
+
+        if !available.is_empty() {
+            match self.routing {
+                Routing::RoundRobin => {
+                    let start = self.cursor.fetch_add(1, Ordering::Relaxed) % available.len();
+                    available.rotate_left(start);
+                }
+                Routing::LatencyWeighted => {
+                    // 尚未测得延迟的节点按 1ms 计算，以便尽快获得样本
+                    let weights = available.iter()
+                        .map(|node| 1.0 / node.state().latency.map_or(1.0, |l| l.as_secs_f64() * 1000.0).max(1.0))
+                        .collect::<Vec<_>>();
+                    let index = WeightedIndex::new(&weights)
+                        .map(|dist| dist.sample(&mut rand::thread_rng()))
+                        .unwrap_or_default();
+                    let first = available.remove(index);
+                    available.sort_by_key(|node| node.state().latency.unwrap_or_default());
+                    available.insert(0, first);
+                }
+            }
+        }
+
+        available.extend(unavailable);
+        available
+    }
+
+    /// 在选出的节点上执行调用，遇到传输层错误时切换到下一个节点
+    pub async fn call<Req, Res, F, Fut>(&self, request: Req, f: F) -> std::result::Result<Response<Res>, Status>
+        where Req: Clone,
+              F: Fn(WalletClient<Channel>, Req) -> Fut,
+              Fut: Future<Output = std::result::Result<Response<Res>, Status>>
+    {
+        self.call_with(&self.nodes, request, WalletClient::new, f, true).await
+    }
+
+    /// 只在选出的一个节点上执行调用，出错时不切换节点
+    ///
+    /// 用于不能盲目重发的请求（如广播交易），由调用方决定如何重试。
+    pub async fn call_once<Req, Res, F, Fut>(&self, request: Req, f: F) -> std::result::Result<Response<Res>, Status>
+        where Req: Clone,
+              F: Fn(WalletClient<Channel>, Req) -> Fut,
+              Fut: Future<Output = std::result::Result<Response<Res>, Status>>
+    {
+        self.call_with(&self.nodes, request, WalletClient::new, f, false).await
+    }
+
+    /// 在 solidity 节点上通过 `WalletSolidity` 服务调用，只能查询已固化的状态
+    ///
+    /// 未配置 solidity 节点时返回 `FailedPrecondition`。
+    pub async fn call_solidity<Req, Res, F, Fut>(&self, request: Req, f: F) -> std::result::Result<Response<Res>, Status>
+        where Req: Clone,
+              F: Fn(WalletSolidityClient<Channel>, Req) -> Fut,
+              Fut: Future<Output = std::result::Result<Response<Res>, Status>>
+    {
+        if self.solidity_nodes.is_empty() {
+            return Err(Status::failed_precondition("no solidity endpoint configured"));
+        }
+
+        self.call_with(&self.solidity_nodes, request, WalletSolidityClient::new, f, true).await
+    }
+
+    async fn call_with<C, Req, Res, F, Fut>(&self, nodes: &[Node], request: Req, client: fn(Channel) -> C, f: F, failover: bool) -> std::result::Result<Response<Res>, Status>
+        where Req: Clone,
+              F: Fn(C, Req) -> Fut,
+              Fut: Future<Output = std::result::Result<Response<Res>, Status>>
+    {
+        let mut last_error = None;
+        let candidates = self.candidates(nodes);
+        let count = if failover { candidates.len() } else { 1 };
+
+        for node in candidates.into_iter().take(count) {
+            let start = Instant::now();
+            match f(client(node.channel.clone()), request.clone()).await {
+                Err(status) if is_transport_error(&status) => {
+                    warn!("Block chain endpoint {} failed: {}", node.endpoint, status);
+                    node.state().healthy = false;
+                    last_error = Some(status);
+                }
+                result => {
+                    let mut state = node.state();
+                    state.healthy = true;
+                    state.record_latency(start.elapsed());
+                    return result;
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| Status::unavailable("no endpoint available")))
+    }
+
+    /// 检查所有节点的健康状况和区块高度
+    pub async fn check_health(&self) {
+        let results = join_all(self.nodes.iter().map(|node| async move {
+            let start = Instant::now();
+            let block = WalletClient::new(node.channel.clone()).get_now_block2(EmptyMessage {}).await;
+            (block.map(|block| block_number(block.get_ref())), start.elapsed())
+        }));
+        let solidity_results = join_all(self.solidity_nodes.iter().map(|node| async move {
+            let start = Instant::now();
+            let block = WalletSolidityClient::new(node.channel.clone()).get_now_block2(EmptyMessage {}).await;
+            (block.map(|block| block_number(block.get_ref())), start.elapsed())
+        }));
+
+        let (results, solidity_results) = futures::join!(results, solidity_results);
+        self.update_health(&self.nodes, results);
+        self.update_health(&self.solidity_nodes, solidity_results);
+    }
+
+    fn update_health(&self, nodes: &[Node], results: Vec<(std::result::Result<i64, Status>, Duration)>) {
+        let highest = results.iter()
+            .filter_map(|(number, _)| number.as_ref().ok())
+            .max()
+            .copied()
+            .unwrap_or_default();
+
+        for (node, (number, latency)) in nodes.iter().zip(results) {
+            let mut state = node.state();
+            match number {
+                Ok(number) => {
+                    let ejected = highest - number > self.max_block_lag;
+                    if ejected && !state.ejected {
+                        warn!("Block chain endpoint {} ejected, head block {} behind {}", node.endpoint, number, highest);
+                    }
+
+                    state.healthy = true;
+                    state.ejected = ejected;
+                    state.head_block = Some(number);
+                    state.record_latency(latency);
+                }
+                Err(status) => {
+                    warn!("Health check of block chain endpoint {} failed: {}", node.endpoint, status);
+                    state.healthy = false;
+                }
+            }
+        }
+    }
+
+    /// 启动后台健康检查任务，连接池被释放后任务自动结束
+    pub fn spawn_health_check(self: &Arc<Self>) -> JoinHandle<()> {
+        let pool = Arc::downgrade(self);
+        let interval = self.health_check_interval;
+
+        tokio::spawn(async move {
+            while let Some(pool) = pool.upgrade() {
+                pool.check_health().await;
+                drop(pool);
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// solidity 节点的数量
+    pub fn solidity_len(&self) -> usize {
+        self.solidity_nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub(crate) fn health_check_interval(&self) -> Duration {
+        self.health_check_interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::apis::{block_header, BlockHeader};
+    use crate::services::mock::{dead_endpoint, MockWallet};
+
+    fn block(number: i64) -> BlockExtention {
+        BlockExtention {
+            block_header: Some(BlockHeader {
+                raw_data: Some(block_header::Raw { number, ..Default::default() }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    async fn node(number: i64, hits: Arc<AtomicUsize>) -> ServiceConfig {
+        ServiceConfig::new(
+            MockWallet::new()
+                .unary("GetNowBlock2", move |_: EmptyMessage| {
+                    hits.fetch_add(1, Ordering::SeqCst);
+                    Ok(block(number))
+                })
+                .serve()
+                .await
+        )
+    }
+
+    async fn head_block(pool: &NodePool) -> std::result::Result<i64, Status> {
+        pool.call(EmptyMessage {}, |mut client, request| async move { client.get_now_block2(request).await })
+            .await
+            .map(|block| block_number(block.get_ref()))
+    }
+
+    #[tokio::test]
+    async fn test_failover() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let pool = NodePool::connect(&PoolConfig {
+            endpoints: vec![ServiceConfig::new(dead_endpoint().await), node(100, hits.clone()).await],
+            ..Default::default()
+        }).await.unwrap();
+
+        assert!(!pool.status()[0].healthy);
+        for _ in 0..4 {
+            assert_eq!(head_block(&pool).await.unwrap(), 100);
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_round_robin() {
+        let hits = [Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0))];
+        let pool = NodePool::connect(&PoolConfig {
+            endpoints: vec![node(100, hits[0].clone()).await, node(100, hits[1].clone()).await],
+            ..Default::default()
+        }).await.unwrap();
+
+        for _ in 0..4 {
+            head_block(&pool).await.unwrap();
+        }
+        assert_eq!(hits[0].load(Ordering::SeqCst), 2);
+        assert_eq!(hits[1].load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_eject_lagging_node() {
+        let hits = [Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0))];
+        let pool = NodePool::connect(&PoolConfig {
+            endpoints: vec![node(100, hits[0].clone()).await, node(80, hits[1].clone()).await],
+            routing: Routing::LatencyWeighted,
+            max_block_lag: 10,
+            ..Default::default()
+        }).await.unwrap();
+
+        pool.check_health().await;
+        let status = pool.status();
+        assert!(!status[0].ejected);
+        assert!(status[1].ejected);
+        assert_eq!(status[1].head_block, Some(80));
+
+        for _ in 0..4 {
+            assert_eq!(head_block(&pool).await.unwrap(), 100);
+        }
+    }
+
+    async fn solidity_node(number: i64, hits: Arc<AtomicUsize>) -> ServiceConfig {
+        ServiceConfig::new(
+            MockWallet::new()
+                .unary("WalletSolidity/GetNowBlock2", move |_: EmptyMessage| {
+                    hits.fetch_add(1, Ordering::SeqCst);
+                    Ok(block(number))
+                })
+                .serve()
+                .await
+        )
+    }
+
+    #[tokio::test]
+    async fn test_solidity_endpoints() {
+        let hits = [Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0))];
+        let pool = NodePool::connect(&PoolConfig {
+            endpoints: vec![node(100, hits[0].clone()).await],
+            solidity_endpoints: vec![solidity_node(80, hits[1].clone()).await, solidity_node(60, hits[2].clone()).await],
+            max_block_lag: 10,
+            ..Default::default()
+        }).await.unwrap();
+
+        // solidity 节点只与 solidity 节点比较区块高度，不会因为落后于全节点被剔除
+        pool.check_health().await;
+        assert!(!pool.status()[0].ejected);
+        let status = pool.solidity_status();
+        assert_eq!(status[0].head_block, Some(80));
+        assert!(!status[0].ejected);
+        assert!(status[1].ejected);
+
+        for _ in 0..2 {
+            let block = pool
+                .call_solidity(EmptyMessage {}, |mut client, request| async move { client.get_now_block2(request).await })
+                .await
+                .unwrap();
+            assert_eq!(block_number(block.get_ref()), 80);
+        }
+        assert_eq!(head_block(&pool).await.unwrap(), 100);
+        assert_eq!(hits.iter().map(|hits| hits.load(Ordering::SeqCst)).collect::<Vec<_>>(), [2, 3, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_no_solidity_endpoint() {
+        let pool = NodePool::connect(&PoolConfig {
+            endpoints: vec![node(100, Arc::new(AtomicUsize::new(0))).await],
+            ..Default::default()
+        }).await.unwrap();
+
+        let status = pool
+            .call_solidity(EmptyMessage {}, |mut client, request| async move { client.get_now_block2(request).await })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn test_all_endpoints_down() {
+        assert!(NodePool::connect(&PoolConfig {
+            endpoints: vec![ServiceConfig::new(dead_endpoint().await)],
+            ..Default::default()
+        }).await.is_err());
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        assert!(NodePool::connect(&PoolConfig {
+            endpoints: vec![node(100, hits).await],
+            solidity_endpoints: vec![ServiceConfig::new(dead_endpoint().await)],
+            ..Default::default()
+        }).await.is_err());
+    }
+}
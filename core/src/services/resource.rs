@@ -1,28 +1,38 @@
-use crate::apis::{FreezeBalanceContract, ResourceCode, Return};
+use crate::apis::{FreezeBalanceContract, ResourceCode, Transaction};
 use crate::Result;
-use crate::services::{ServiceAgent, Transfer};
+use crate::services::{ServiceAgent, TxOptions};
+use crate::signer::Signer;
+use crate::transaction::BroadcastReceipt;
 
 #[async_trait]
 pub trait Resource {
-    async fn freeze_balance(&mut self, balance: i64, duration: i64, resource: ResourceCode) -> Result<Return>;
+    /// 由节点创建冻结余额交易，返回待签名的交易
+    async fn build_freeze_balance(&mut self, balance: i64, duration: i64, resource: ResourceCode, options: &TxOptions) -> Result<Transaction>;
+    async fn freeze_balance(&mut self, balance: i64, duration: i64, resource: ResourceCode, options: &TxOptions) -> Result<BroadcastReceipt>;
 }
 
 #[async_trait]
-impl<'s> Resource for ServiceAgent<'s> {
-    async fn freeze_balance(&mut self, balance: i64, duration: i64, resource: ResourceCode) -> Result<Return> {
-        let trx_ext = self.client
-            .freeze_balance2(FreezeBalanceContract {
-                owner_address: self.key.address().into(),
-                frozen_balance: balance,
-                frozen_duration: duration,
-                resource: resource.into(),
-                receiver_address: vec![],
-            })
+impl<'s, S: Signer> Resource for ServiceAgent<'s, S> {
+    async fn build_freeze_balance(&mut self, balance: i64, duration: i64, resource: ResourceCode, options: &TxOptions) -> Result<Transaction> {
+        let contract = FreezeBalanceContract {
+            owner_address: self.signer.address().into(),
+            frozen_balance: balance,
+            frozen_duration: duration,
+            resource: resource.into(),
+            receiver_address: vec![],
+        };
+        let trx_ext = self
+            .call(contract, |mut client, contract| async move { client.freeze_balance2(contract).await })
             .await?
             .into_inner();
 
-        Ok(
-            self.sign_and_broadcast(trx_ext).await?
-        )
+        self.build(trx_ext, options)
     }
-} 
\ No newline at end of file
+
+    async fn freeze_balance(&mut self, balance: i64, duration: i64, resource: ResourceCode, options: &TxOptions) -> Result<BroadcastReceipt> {
+        let transaction = self.build_freeze_balance(balance, duration, resource, options).await?;
+        let signed = self.sign(transaction).await?;
+
+        self.broadcast_transaction(signed).await
+    }
+}
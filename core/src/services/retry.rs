@@ -0,0 +1,207 @@
+use std::future::Future;
+use std::time::Duration;
+
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tonic::{Code, Status};
+
+use crate::services::pool::is_transport_error;
+
+/// RPC 调用的重试策略
+///
+/// 等待时间从 `initial_backoff` 开始按 `multiplier` 指数增长，不超过 `max_backoff`，
+/// 并在此基础上增加 `±jitter` 比例的随机抖动。传输层错误总是可以重试。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 最大尝试次数（包括第一次），为 1 时不重试
+    pub max_attempts: u32,
+    /// 首次重试前的等待时间（毫秒）
+    pub initial_backoff: u64,
+    /// 最长等待时间（毫秒）
+    pub max_backoff: u64,
+    /// 每次重试等待时间的增长倍数
+    pub multiplier: f64,
+    /// 随机抖动比例，取值 0 ~ 1
+    pub jitter: f64,
+    /// 可重试的状态码，如 `["Unavailable", "DeadlineExceeded"]`
+    #[serde(serialize_with = "serialize_codes", deserialize_with = "deserialize_codes")]
+    pub retryable_codes: Vec<Code>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: 200,
+            max_backoff: 5_000,
+            multiplier: 2.0,
+            jitter: 0.2,
+            retryable_codes: vec![Code::Unavailable, Code::DeadlineExceeded, Code::ResourceExhausted],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 不进行重试的策略
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_retryable(&self, status: &Status) -> bool {
+        self.retryable_codes.contains(&status.code()) || is_transport_error(status)
+    }
+
+    /// 第 `attempt` 次尝试失败后的等待时间，`attempt` 从 1 开始
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = (self.initial_backoff as f64 * self.multiplier.powi(exponent))
+            .min(self.max_backoff as f64);
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = if jitter > 0.0 {
+            rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter)
+        } else {
+            1.0
+        };
+
+        Duration::from_secs_f64((backoff * factor).max(0.0) / 1000.0)
+    }
+
+    /// 执行调用，遇到可重试的错误时等待后重试
+    pub async fn retry<T, F, Fut>(&self, mut f: F) -> Result<T, Status>
+        where F: FnMut() -> Fut,
+              Fut: Future<Output = Result<T, Status>>
+    {
+        let mut attempt = 1;
+        loop {
+            match f().await {
+                Err(status) if attempt < self.max_attempts && self.is_retryable(&status) => {
+                    let backoff = self.backoff(attempt);
+                    debug!("attempt {} failed: {}, retry after {:?}", attempt, status, backoff);
+
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+fn code_name(code: Code) -> &'static str {
+    match code {
+        Code::Ok => "Ok",
+        Code::Cancelled => "Cancelled",
+        Code::Unknown => "Unknown",
+        Code::InvalidArgument => "InvalidArgument",
+        Code::DeadlineExceeded => "DeadlineExceeded",
+        Code::NotFound => "NotFound",
+        Code::AlreadyExists => "AlreadyExists",
+        Code::PermissionDenied => "PermissionDenied",
+        Code::ResourceExhausted => "ResourceExhausted",
+        Code::FailedPrecondition => "FailedPrecondition",
+        Code::Aborted => "Aborted",
+        Code::OutOfRange => "OutOfRange",
+        Code::Unimplemented => "Unimplemented",
+        Code::Internal => "Internal",
+        Code::Unavailable => "Unavailable",
+        Code::DataLoss => "DataLoss",
+        Code::Unauthenticated => "Unauthenticated",
+        _ => "Unknown",
+    }
+}
+
+fn serialize_codes<S: Serializer>(codes: &[Code], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(codes.iter().map(|code| code_name(*code)))
+}
+
+fn deserialize_codes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Code>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|name| {
+            (0..=16).map(Code::from_i32)
+                .find(|code| code_name(*code) == name)
+                .ok_or_else(|| serde::de::Error::custom(format!("unknown status code: {}", name)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            initial_backoff: 1,
+            jitter: 0.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            initial_backoff: 100,
+            max_backoff: 1000,
+            jitter: 0.0,
+            ..Default::default()
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(10), Duration::from_millis(1000));
+
+        let policy = RetryPolicy { jitter: 0.5, ..policy };
+        for _ in 0..100 {
+            let backoff = policy.backoff(1);
+            assert!(backoff >= Duration::from_millis(50) && backoff <= Duration::from_millis(150));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        let attempts = AtomicU32::new(0);
+        let result = policy().retry(|| async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err(Status::unavailable("busy")),
+                n => Ok(n),
+            }
+        }).await;
+
+        assert_eq!(result.unwrap(), 2);
+
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = policy().retry(|| async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(Status::invalid_argument("bad request"))
+        }).await;
+
+        assert_eq!(result.unwrap_err().code(), Code::InvalidArgument);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = policy().retry(|| async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(Status::deadline_exceeded("slow"))
+        }).await;
+
+        assert_eq!(result.unwrap_err().code(), Code::DeadlineExceeded);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_codes_serde() {
+        let policy: RetryPolicy = serde_json::from_str(r#"{"retryable_codes": ["Unavailable", "Aborted"]}"#).unwrap();
+        assert_eq!(policy.retryable_codes, vec![Code::Unavailable, Code::Aborted]);
+        assert_eq!(policy.max_attempts, 3);
+
+        assert!(serde_json::from_str::<RetryPolicy>(r#"{"retryable_codes": ["Sometimes"]}"#).is_err());
+    }
+}
@@ -1,67 +1,412 @@
 use crate::key::Address;
 use crate::Result;
-use crate::services::{Service, ServiceAgent};
-use crate::apis::{TransferContract, Return, TriggerSmartContract, Transaction, TransactionExtention};
-use prost::Message;
-use crate::error::Error;
+use crate::services::{ServiceAgent, TxOptions};
+use crate::apis::{TransferContract, TriggerSmartContract, Transaction, TransactionExtention};
+use crate::signer::Signer;
+use crate::transaction::BroadcastReceipt;
 
 #[async_trait]
 pub trait Transfer {
-    async fn transfer(&mut self, to: &Address, amount: i64) -> Result<Return>;
-    async fn contract_transfer(&mut self, contract: &Address, data: Vec<u8>) -> Result<Return>;
-    async fn sign_and_broadcast(&mut self, transaction: TransactionExtention) -> Result<Return>;
+    /// 由节点创建 TRX 转账交易，返回待签名的交易
+    async fn build_transfer(&mut self, to: &Address, amount: i64, options: &TxOptions) -> Result<Transaction>;
+    /// 由节点创建合约调用交易，返回待签名的交易
+    async fn build_contract_transfer(&mut self, contract: &Address, data: Vec<u8>, options: &TxOptions) -> Result<Transaction>;
+
+    async fn transfer(&mut self, to: &Address, amount: i64, options: &TxOptions) -> Result<BroadcastReceipt>;
+    async fn contract_transfer(&mut self, contract: &Address, data: Vec<u8>, options: &TxOptions) -> Result<BroadcastReceipt>;
+    async fn sign_and_broadcast(&mut self, transaction: TransactionExtention, options: &TxOptions) -> Result<BroadcastReceipt>;
 }
 
 #[async_trait]
-impl<'s> Transfer for ServiceAgent<'s> {
-    async fn transfer(&mut self, to: &Address, amount: i64) -> Result<Return> {
-        let trx_ext = self.client
-            .create_transaction2(TransferContract {
-                owner_address: self.key.address().into(),
-                to_address: to.into(),
-                amount,
-            })
+impl<'s, S: Signer> Transfer for ServiceAgent<'s, S> {
+    async fn build_transfer(&mut self, to: &Address, amount: i64, options: &TxOptions) -> Result<Transaction> {
+        let contract = TransferContract {
+            owner_address: self.signer.address().into(),
+            to_address: to.into(),
+            amount,
+        };
+        let trx_ext = self
+            .call(contract, |mut client, contract| async move { client.create_transaction2(contract).await })
             .await?
             .into_inner();
 
-        self.sign_and_broadcast(trx_ext).await
+        self.build(trx_ext, options)
     }
 
-    async fn contract_transfer(&mut self, contract: &Address, data: Vec<u8>) -> Result<Return> {
-        let trx_ext = self.client
-            .trigger_contract(TriggerSmartContract {
-                owner_address: self.key.address().into(),
-                contract_address: contract.into(),
-                call_value: 0,
-                data,
-                call_token_value: 0,
-                token_id: 0
-            })
+    async fn build_contract_transfer(&mut self, contract: &Address, data: Vec<u8>, options: &TxOptions) -> Result<Transaction> {
+        let options = self.options.merge(options);
+        let contract = TriggerSmartContract {
+            owner_address: self.signer.address().into(),
+            contract_address: contract.into(),
+            call_value: options.call_value.unwrap_or_default(),
+            data,
+            call_token_value: options.call_token_value.unwrap_or_default(),
+            token_id: options.token_id.unwrap_or_default(),
+        };
+        let trx_ext = self
+            .call(contract, |mut client, contract| async move { client.trigger_contract(contract).await })
             .await?
             .into_inner();
 
-        self.sign_and_broadcast(trx_ext).await
+        self.build(trx_ext, &options)
+    }
+
+    async fn transfer(&mut self, to: &Address, amount: i64, options: &TxOptions) -> Result<BroadcastReceipt> {
+        let transaction = self.build_transfer(to, amount, options).await?;
+        let signed = self.sign(transaction).await?;
+
+        self.broadcast_transaction(signed).await
+    }
+
+    async fn contract_transfer(&mut self, contract: &Address, data: Vec<u8>, options: &TxOptions) -> Result<BroadcastReceipt> {
+        let transaction = self.build_contract_transfer(contract, data, options).await?;
+        let signed = self.sign(transaction).await?;
+
+        self.broadcast_transaction(signed).await
+    }
+
+    async fn sign_and_broadcast(&mut self, transaction_ext: TransactionExtention, options: &TxOptions) -> Result<BroadcastReceipt> {
+        let transaction = self.build(transaction_ext, options)?;
+        let signed = self.sign(transaction).await?;
+
+        self.broadcast_transaction(signed).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tonic::Status;
+
+    use super::*;
+    use crate::apis::{transaction, BytesMessage, Transaction};
+    use crate::apis::r#return::ResponseCode;
+    use crate::apis::transaction::contract::ContractType;
+    use crate::key::PrivateKey;
+    use crate::services::mock::MockWallet;
+    use crate::services::{PoolConfig, RetryPolicy, Service, ServiceConfig};
+    use crate::apis::Return;
+    use crate::error::Error;
+    use crate::utils::transaction_id;
+
+    fn created_transaction(_: TransferContract) -> std::result::Result<TransactionExtention, Status> {
+        Ok(TransactionExtention {
+            transaction: Some(Transaction {
+                raw_data: Some(transaction::Raw { timestamp: 1, ..Default::default() }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    fn retry_policy() -> RetryPolicy {
+        RetryPolicy { initial_backoff: 1, ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_skipped_when_transaction_landed() {
+        let broadcasts = Arc::new(AtomicUsize::new(0));
+        let counter = broadcasts.clone();
+        let endpoint = MockWallet::new()
+            .unary("CreateTransaction2", created_transaction)
+            .unary("BroadcastTransaction", move |_: Transaction| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Err::<Return, _>(Status::unavailable("timeout"))
+            })
+            .unary("GetTransactionById", |_: BytesMessage| {
+                Ok(created_transaction(TransferContract::default())?.transaction.unwrap())
+            })
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate()).with_retry_policy(retry_policy());
+
+        let to = PrivateKey::generate();
+        let receipt = agent.transfer(to.address(), 1, &TxOptions::default()).await.unwrap();
+        assert!(receipt.r#return.result);
+        assert_eq!(broadcasts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_retry_duplicated() {
+        let broadcasts = Arc::new(AtomicUsize::new(0));
+        let counter = broadcasts.clone();
+        let endpoint = MockWallet::new()
+            .unary("CreateTransaction2", created_transaction)
+            .unary("BroadcastTransaction", move |_: Transaction| {
+                match counter.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(Status::unavailable("timeout")),
+                    _ => Ok(Return { result: false, code: ResponseCode::DupTransactionError as i32, message: vec![] }),
+                }
+            })
+            .unary("GetTransactionById", |_: BytesMessage| Ok(Transaction::default()))
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate()).with_retry_policy(retry_policy());
+
+        let to = PrivateKey::generate();
+        let receipt = agent.transfer(to.address(), 1, &TxOptions::default()).await.unwrap();
+        assert!(receipt.r#return.result);
+        assert_eq!(broadcasts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_outcome_unknown() {
+        let broadcasts = Arc::new(AtomicUsize::new(0));
+        let counter = broadcasts.clone();
+        let endpoint = MockWallet::new()
+            .unary("CreateTransaction2", created_transaction)
+            .unary("BroadcastTransaction", move |_: Transaction| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Err::<Return, _>(Status::unavailable("timeout"))
+            })
+            .unary("GetTransactionById", |_: BytesMessage| Err::<Transaction, _>(Status::internal("database error")))
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate()).with_retry_policy(retry_policy());
+
+        // 无法确认交易是否上链时不能再次广播
+        let to = PrivateKey::generate();
+        let error = agent.transfer(to.address(), 1, &TxOptions::default()).await.unwrap_err();
+        assert!(matches!(error, Error::BroadcastUnknown { ref txid, .. } if txid.len() == 64));
+        assert_eq!(broadcasts.load(Ordering::SeqCst), 1);
+    }
+
+    async fn accepting_node(accepted: Arc<AtomicUsize>) -> ServiceConfig {
+        let found = accepted.clone();
+        let endpoint = MockWallet::new()
+            .unary("CreateTransaction2", created_transaction)
+            .unary("BroadcastTransaction", move |_: Transaction| {
+                // 第一次广播被接收后超时，之后的广播都是重复交易
+                match accepted.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(Status::unavailable("timeout")),
+                    _ => Ok(Return { result: false, code: ResponseCode::DupTransactionError as i32, message: vec![] }),
+                }
+            })
+            .unary("GetTransactionById", move |_: BytesMessage| {
+                match found.load(Ordering::SeqCst) {
+                    0 => Ok(Transaction::default()),
+                    _ => Ok(created_transaction(TransferContract::default())?.transaction.unwrap()),
+                }
+            })
+            .serve()
+            .await;
+
+        ServiceConfig::new(endpoint)
     }
 
-    async fn sign_and_broadcast(&mut self, transaction_ext: TransactionExtention) -> Result<Return> {
-        debug!("created transaction, id = {}", hex::encode(&transaction_ext.txid));
-        let mut transaction = transaction_ext.transaction.ok_or(Error::EmptyTransaction)?;
+    #[tokio::test]
+    async fn test_broadcast_accepted_then_timeout() {
+        let broadcasts = Arc::new(AtomicUsize::new(0));
+        let service = Service::with_pool_config(PoolConfig {
+            endpoints: vec![accepting_node(broadcasts.clone()).await, accepting_node(broadcasts.clone()).await],
+            ..Default::default()
+        }).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate()).with_retry_policy(retry_policy());
 
-        if let Some(mut raw_data) = transaction.raw_data.as_mut() {
-            // set fee limit
-            raw_data.fee_limit = 10_000000;
+        // 超时后不能直接切换到另一个节点重发，否则已成功的交易会被当作重复交易报错
+        let to = PrivateKey::generate();
+        let receipt = agent.transfer(to.address(), 1, &TxOptions::default()).await.unwrap();
+        assert!(receipt.r#return.result);
+        assert_eq!(broadcasts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_rejected_before_signing() {
+        let broadcasts = Arc::new(AtomicUsize::new(0));
+        let counter = broadcasts.clone();
+        let endpoint = MockWallet::new()
+            .unary("TriggerContract", |_: TriggerSmartContract| {
+                Ok(TransactionExtention {
+                    result: Some(Return {
+                        result: false,
+                        code: ResponseCode::ContractValidateError as i32,
+                        message: b"contract validate error : account does not exist".to_vec(),
+                    }),
+                    energy_used: 1200,
+                    ..Default::default()
+                })
+            })
+            .unary("BroadcastTransaction", move |_: Transaction| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(Return { result: true, ..Default::default() })
+            })
+            .serve()
+            .await;
 
-            let mut buf = Vec::with_capacity(raw_data.encoded_len());
-            raw_data.encode(&mut buf).unwrap();
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate());
 
-            transaction.signature = vec![Vec::from(self.key.sign(&buf).as_ref())];
+        let contract = PrivateKey::generate();
+        match agent.contract_transfer(contract.address(), vec![], &TxOptions::default()).await {
+            Err(Error::Transaction { error, energy_used, .. }) => {
+                assert_eq!(error.code, ResponseCode::ContractValidateError);
+                assert_eq!(error.message, "contract validate error : account does not exist");
+                assert_eq!(energy_used, 1200);
+            }
+            other => panic!("unexpected result: {:?}", other),
         }
+        assert_eq!(broadcasts.load(Ordering::SeqCst), 0);
+    }
 
-        Ok(
-            self.client
-                .broadcast_transaction(transaction)
-                .await?
-                .into_inner()
-        )
+    #[tokio::test]
+    async fn test_options_applied() {
+        let triggered = Arc::new(Mutex::new(None));
+        let broadcasted = Arc::new(Mutex::new(None));
+        let (trigger, broadcast) = (triggered.clone(), broadcasted.clone());
+        let endpoint = MockWallet::new()
+            .unary("TriggerContract", move |contract: TriggerSmartContract| {
+                *trigger.lock().unwrap() = Some(contract);
+                Ok(TransactionExtention {
+                    transaction: Some(Transaction {
+                        raw_data: Some(transaction::Raw {
+                            contract: vec![transaction::Contract {
+                                r#type: ContractType::TriggerSmartContract as i32,
+                                ..Default::default()
+                            }],
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                    result: Some(Return { result: true, ..Default::default() }),
+                    ..Default::default()
+                })
+            })
+            .unary("BroadcastTransaction", move |transaction: Transaction| {
+                *broadcast.lock().unwrap() = Some(transaction);
+                Ok(Return { result: true, ..Default::default() })
+            })
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate())
+            .with_options(TxOptions::new().fee_limit(30_000000).memo("default memo"));
+
+        let contract = PrivateKey::generate();
+        agent.contract_transfer(contract.address(), vec![], &TxOptions::new().memo("order #42").call_value(5))
+            .await
+            .unwrap();
+
+        assert_eq!(triggered.lock().unwrap().as_ref().unwrap().call_value, 5);
+        let raw_data = broadcasted.lock().unwrap().take().unwrap().raw_data.unwrap();
+        assert_eq!(raw_data.fee_limit, 30_000000);
+        assert_eq!(raw_data.data, b"order #42");
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_receipt_txid() {
+        let endpoint = MockWallet::new()
+            .unary("CreateTransaction2", |contract: TransferContract| {
+                let mut transaction_ext = created_transaction(contract)?;
+                transaction_ext.txid = transaction_id(transaction_ext.transaction.as_ref().unwrap().raw_data.as_ref().unwrap()).to_vec();
+                Ok(transaction_ext)
+            })
+            .unary("BroadcastTransaction", |_: Transaction| Ok(Return { result: true, ..Default::default() }))
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate());
+
+        let to = PrivateKey::generate();
+        let receipt = agent.transfer(to.address(), 1, &TxOptions::new().memo("memo")).await.unwrap();
+        let raw_data = receipt.signed_transaction.raw_data.as_ref().unwrap();
+
+        assert_eq!(raw_data.data, b"memo");
+        assert_eq!(receipt.txid, transaction_id(raw_data));
+        assert_eq!(receipt.signed_transaction.signature.len(), 1);
+    }
+
+    /// 模拟远程签名服务
+    struct RemoteSigner(PrivateKey);
+
+    #[async_trait]
+    impl Signer for RemoteSigner {
+        fn address(&self) -> &crate::key::Address {
+            self.0.address()
+        }
+
+        async fn sign_hash(&self, hash: &[u8; 32]) -> Result<Vec<u8>> {
+            self.0.sign_hash(hash).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_sign_broadcast() {
+        let owner = Arc::new(Mutex::new(vec![]));
+        let captured = owner.clone();
+        let endpoint = MockWallet::new()
+            .unary("CreateTransaction2", move |contract: TransferContract| {
+                *captured.lock().unwrap() = contract.owner_address.clone();
+                created_transaction(contract)
+            })
+            .unary("BroadcastTransaction", |_: Transaction| Ok(Return { result: true, ..Default::default() }))
+            .serve()
+            .await;
+
+        let key = PrivateKey::generate();
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(RemoteSigner(PrivateKey::from_bytes(&*key.to_secret_bytes()).unwrap()));
+
+        let to = PrivateKey::generate();
+        let transaction = agent.build_transfer(to.address(), 1, &TxOptions::default()).await.unwrap();
+        assert!(transaction.signature.is_empty());
+        assert_eq!(*owner.lock().unwrap(), Vec::from(key.address()));
+
+        let signed = agent.sign(transaction).await.unwrap();
+        assert_eq!(signed.transaction.signature, vec![key.sign_hash(&signed.txid).await.unwrap()]);
+
+        let receipt = agent.broadcast_transaction(signed.clone()).await.unwrap();
+        assert_eq!(receipt.txid, signed.txid);
+    }
+
+    #[tokio::test]
+    async fn test_txid_mismatch() {
+        let endpoint = MockWallet::new()
+            .unary("CreateTransaction2", |contract: TransferContract| {
+                Ok(TransactionExtention { txid: vec![0; 32], ..created_transaction(contract)? })
+            })
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate());
+
+        let to = PrivateKey::generate();
+        assert!(matches!(
+            agent.transfer(to.address(), 1, &TxOptions::default()).await,
+            Err(Error::TxidMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_rejected() {
+        let endpoint = MockWallet::new()
+            .unary("CreateTransaction2", created_transaction)
+            .unary("BroadcastTransaction", |_: Transaction| {
+                Ok(Return { result: false, code: ResponseCode::Sigerror as i32, message: b"invalid signature".to_vec() })
+            })
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate());
+
+        let to = PrivateKey::generate();
+        match agent.transfer(to.address(), 1, &TxOptions::default()).await {
+            Err(Error::Broadcast(e)) => {
+                assert_eq!(e.code, ResponseCode::Sigerror);
+                assert_eq!(e.message, "invalid signature");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
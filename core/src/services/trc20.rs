@@ -0,0 +1,317 @@
+use std::fmt;
+
+use ethabi::{Function, Token, Uint as U256};
+
+use crate::error::Error;
+use crate::key::Address;
+use crate::predefined::trc20_function;
+use crate::Result;
+use crate::services::{ServiceAgent, Transfer, TxOptions};
+use crate::signer::Signer;
+use crate::transaction::BroadcastReceipt;
+
+/// 绑定了合约地址的 TRC20 代币，数量都是不带小数位的最小单位
+///
+/// 只读方法通过 `trigger_constant_contract` 调用，不消耗资源。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trc20Token {
+    address: Address,
+}
+
+impl Trc20Token {
+    pub fn new(address: Address) -> Self {
+        Self { address }
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub async fn balance_of<S: Signer>(&self, agent: &ServiceAgent<'_, S>, owner: &Address) -> Result<U256> {
+        into_uint(self.call(agent, "balanceOf", &[(*owner).into()]).await?)
+    }
+
+    pub async fn decimals<S: Signer>(&self, agent: &ServiceAgent<'_, S>) -> Result<u8> {
+        let decimals = into_uint(self.call(agent, "decimals", &[]).await?)?;
+        if decimals > U256::from(u8::MAX) {
+            return Err(ethabi::Error::InvalidData.into());
+        }
+
+        Ok(decimals.low_u32() as u8)
+    }
+
+    pub async fn symbol<S: Signer>(&self, agent: &ServiceAgent<'_, S>) -> Result<String> {
+        into_string(self.call(agent, "symbol", &[]).await?)
+    }
+
+    pub async fn name<S: Signer>(&self, agent: &ServiceAgent<'_, S>) -> Result<String> {
+        into_string(self.call(agent, "name", &[]).await?)
+    }
+
+    pub async fn total_supply<S: Signer>(&self, agent: &ServiceAgent<'_, S>) -> Result<U256> {
+        into_uint(self.call(agent, "totalSupply", &[]).await?)
+    }
+
+    pub async fn allowance<S: Signer>(&self, agent: &ServiceAgent<'_, S>, owner: &Address, spender: &Address) -> Result<U256> {
+        into_uint(self.call(agent, "allowance", &[(*owner).into(), (*spender).into()]).await?)
+    }
+
+    /// 由签名者转账给 `to`
+    pub async fn transfer<S: Signer>(
+        &self,
+        agent: &mut ServiceAgent<'_, S>,
+        to: &Address,
+        amount: U256,
+        options: &TxOptions,
+    ) -> Result<BroadcastReceipt> {
+        self.send(agent, "transfer", &[(*to).into(), Token::Uint(amount)], options).await
+    }
+
+    /// 允许 `spender` 从签名者的账户转出最多 `amount`
+    pub async fn approve<S: Signer>(
+        &self,
+        agent: &mut ServiceAgent<'_, S>,
+        spender: &Address,
+        amount: U256,
+        options: &TxOptions,
+    ) -> Result<BroadcastReceipt> {
+        self.send(agent, "approve", &[(*spender).into(), Token::Uint(amount)], options).await
+    }
+
+    /// 使用签名者获得的授权，从 `from` 转账给 `to`
+    pub async fn transfer_from<S: Signer>(
+        &self,
+        agent: &mut ServiceAgent<'_, S>,
+        from: &Address,
+        to: &Address,
+        amount: U256,
+        options: &TxOptions,
+    ) -> Result<BroadcastReceipt> {
+        self.send(agent, "transferFrom", &[(*from).into(), (*to).into(), Token::Uint(amount)], options).await
+    }
+
+    async fn call<S: Signer>(&self, agent: &ServiceAgent<'_, S>, name: &str, args: &[Token]) -> Result<Token> {
+        agent.call_function(&self.address, &trc20(name), args).await?.pop().ok_or_else(|| ethabi::Error::InvalidData.into())
+    }
+
+    async fn send<S: Signer>(
+        &self,
+        agent: &mut ServiceAgent<'_, S>,
+        name: &str,
+        args: &[Token],
+        options: &TxOptions,
+    ) -> Result<BroadcastReceipt> {
+        let data = trc20(name).encode_input(args)?;
+
+        agent.contract_transfer(&self.address, data, options).await
+    }
+}
+
+fn trc20(name: &str) -> Function {
+    trc20_function(name).expect("standard TRC20 function")
+}
+
+fn into_uint(token: Token) -> Result<U256> {
+    token.into_uint().ok_or_else(|| ethabi::Error::InvalidData.into())
+}
+
+fn into_string(token: Token) -> Result<String> {
+    token.into_string().ok_or_else(|| ethabi::Error::InvalidData.into())
+}
+
+/// U256 能表示的最大小数位数
+const MAX_DECIMALS: u8 = 77;
+
+/// 带小数位的代币数量，如 USDT 的小数位为 6 时 `12.5` 对应 `12500000`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAmount {
+    value: U256,
+    decimals: u8,
+}
+
+impl TokenAmount {
+    /// 由最小单位的数量创建
+    pub fn new(value: U256, decimals: u8) -> Self {
+        Self { value, decimals }
+    }
+
+    /// 解析十进制数量，如 `12.5`，小数部分不能超过 `decimals` 位
+    pub fn parse(s: &str, decimals: u8) -> Result<Self> {
+        let invalid = || Error::InvalidAmount(s.into());
+        if decimals > MAX_DECIMALS {
+            return Err(invalid());
+        }
+
+        let (integer, fraction) = match s.trim().split_once('.') {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (s.trim(), ""),
+        };
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if (integer.is_empty() && fraction.is_empty()) || !is_digits(integer) || !is_digits(fraction)
+            || fraction.len() > decimals as usize
+        {
+            return Err(invalid());
+        }
+
+        let digits = format!("{}{}{}", integer, fraction, "0".repeat(decimals as usize - fraction.len()));
+        let value = U256::from_dec_str(&digits).map_err(|_| invalid())?;
+
+        Ok(Self { value, decimals })
+    }
+
+    /// 解析带代币符号的数量，如 `12.5 USDT`，符号不区分大小写，可以省略
+    pub fn parse_with_symbol(s: &str, decimals: u8, symbol: &str) -> Result<Self> {
+        let s = s.trim();
+        let amount = match s.len().checked_sub(symbol.len()) {
+            Some(end) if s.is_char_boundary(end) && s[end..].eq_ignore_ascii_case(symbol) => &s[..end],
+            _ => s,
+        };
+
+        Self::parse(amount, decimals)
+    }
+
+    pub fn value(&self) -> U256 {
+        self.value
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// 带代币符号的字符串，如 `12.5 USDT`
+    pub fn with_symbol(&self, symbol: &str) -> String {
+        format!("{} {}", self, symbol)
+    }
+}
+
+impl From<TokenAmount> for U256 {
+    fn from(amount: TokenAmount) -> Self {
+        amount.value
+    }
+}
+
+/// 十进制格式，省略小数部分末尾的 0
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.value.to_string();
+        let decimals = self.decimals as usize;
+        if decimals == 0 {
+            return f.write_str(&digits);
+        }
+
+        let digits = format!("{:0>width$}", digits, width = decimals + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - decimals);
+        let fraction = fraction.trim_end_matches('0');
+
+        if fraction.is_empty() {
+            f.write_str(integer)
+        } else {
+            write!(f, "{}.{}", integer, fraction)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::apis::{transaction, Return, Transaction, TransactionExtention, TriggerSmartContract};
+    use crate::key::PrivateKey;
+    use crate::services::mock::MockWallet;
+    use crate::services::{Service, ServiceConfig};
+
+    fn usdt() -> Address {
+        "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_constant_call() {
+        let owner = PrivateKey::generate();
+        let owner_address = *owner.address();
+        let endpoint = MockWallet::new()
+            .unary("TriggerConstantContract", move |request: TriggerSmartContract| {
+                assert_eq!(request.contract_address, Vec::from(usdt()));
+                assert_eq!(request.owner_address, Vec::from(owner_address));
+
+                let output = match hex::encode(&request.data[..4]).as_str() {
+                    "70a08231" => {
+                        assert_eq!(&request.data[16..36], &owner_address.evm_address());
+                        ethabi::encode(&[Token::Uint(12_500000.into())])
+                    }
+                    "313ce567" => ethabi::encode(&[Token::Uint(6.into())]),
+                    "95d89b41" => ethabi::encode(&[Token::String("USDT".into())]),
+                    _ => vec![],
+                };
+                Ok(TransactionExtention { constant_result: vec![output], ..Default::default() })
+            })
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let agent = service.agent(owner);
+        let token = Trc20Token::new(usdt());
+
+        let balance = token.balance_of(&agent, &owner_address).await.unwrap();
+        assert_eq!(balance, 12_500000.into());
+        let decimals = token.decimals(&agent).await.unwrap();
+        let symbol = token.symbol(&agent).await.unwrap();
+        assert_eq!(TokenAmount::new(balance, decimals).with_symbol(&symbol), "12.5 USDT");
+
+        // 空的返回数据无法解码
+        assert!(matches!(token.total_supply(&agent).await, Err(Error::Abi(_))));
+    }
+
+    #[tokio::test]
+    async fn test_transfer() {
+        let to = Address::from_evm([0x11; 20]);
+        let broadcasted = Arc::new(Mutex::new(None));
+        let broadcast = broadcasted.clone();
+        let endpoint = MockWallet::new()
+            .unary("TriggerContract", move |request: TriggerSmartContract| {
+                assert_eq!(request.data, trc20("transfer").encode_input(&[to.into(), Token::Uint(U256::exp10(20))]).unwrap());
+
+                Ok(TransactionExtention {
+                    transaction: Some(Transaction {
+                        raw_data: Some(transaction::Raw { timestamp: 1, ..Default::default() }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+            })
+            .unary("BroadcastTransaction", move |transaction: Transaction| {
+                *broadcast.lock().unwrap() = Some(transaction);
+                Ok(Return { result: true, ..Default::default() })
+            })
+            .serve()
+            .await;
+
+        let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+        let mut agent = service.agent(PrivateKey::generate());
+
+        let amount = TokenAmount::parse_with_symbol("100 USDT", 18, "USDT").unwrap();
+        Trc20Token::new(usdt()).transfer(&mut agent, &to, amount.into(), &TxOptions::default()).await.unwrap();
+        assert_eq!(broadcasted.lock().unwrap().take().unwrap().signature.len(), 1);
+    }
+
+    #[test]
+    fn test_token_amount() {
+        let amount = TokenAmount::parse("12.5", 6).unwrap();
+        assert_eq!(amount.value(), 12_500000.into());
+        assert_eq!(amount.to_string(), "12.5");
+        assert_eq!(TokenAmount::parse_with_symbol("12.5 usdt", 6, "USDT").unwrap(), amount);
+        assert_eq!(TokenAmount::parse_with_symbol("12.5", 6, "USDT").unwrap(), amount);
+
+        assert_eq!(TokenAmount::new(1.into(), 6).to_string(), "0.000001");
+        assert_eq!(TokenAmount::new(0.into(), 6).to_string(), "0");
+        assert_eq!(TokenAmount::new(1_000000.into(), 6).with_symbol("USDT"), "1 USDT");
+        assert_eq!(TokenAmount::new(42.into(), 0).to_string(), "42");
+        assert_eq!(TokenAmount::parse(".5", 1).unwrap().value(), 5.into());
+        assert_eq!(TokenAmount::parse("7.", 2).unwrap().value(), 700.into());
+
+        for invalid in ["", ".", "1.2345678", "-1", "1e6", "1.2.3", "12.5 TRX"] {
+            assert!(matches!(TokenAmount::parse(invalid, 6), Err(Error::InvalidAmount(_))), "{}", invalid);
+        }
+        assert!(TokenAmount::parse(&U256::MAX.to_string(), 1).is_err());
+    }
+}
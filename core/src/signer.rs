@@ -0,0 +1,168 @@
+use digest::consts::{U32, U64};
+use digest::{BlockInput, FixedOutput, Output, Reset, Update};
+use k256::ecdsa::recoverable;
+use k256::ecdsa::signature::DigestSigner;
+use sha2::Sha256;
+
+use crate::apis::Transaction;
+use crate::error::Error;
+use crate::key::{Address, PrivateKey, PublicKey};
+use crate::transaction::SignedTransaction;
+use crate::utils::transaction_id;
+use crate::Result;
+
+/// 交易签名者
+///
+/// 除本地的 [`PrivateKey`] 外，也可以由远程签名服务、加密的 keystore 或离线设备实现。
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// 签名者的地址
+    fn address(&self) -> &Address;
+
+    /// 对交易 `raw_data` 的 SHA-256 摘要签名，返回 65 字节的可恢复签名 `r || s || v`
+    async fn sign_hash(&self, hash: &[u8; 32]) -> Result<Vec<u8>>;
+}
+
+#[async_trait]
+impl Signer for PrivateKey {
+    fn address(&self) -> &Address {
+        PrivateKey::address(self)
+    }
+
+    async fn sign_hash(&self, hash: &[u8; 32]) -> Result<Vec<u8>> {
+        let signature: recoverable::Signature = self.key().try_sign_digest(Prehashed::new(hash))?;
+        Ok(signature.as_ref().to_vec())
+    }
+}
+
+/// 对交易签名，签名追加到 `Transaction.signature` 中
+pub async fn sign_transaction<S: Signer + ?Sized>(signer: &S, mut transaction: Transaction) -> Result<SignedTransaction> {
+    let raw_data = transaction.raw_data.as_ref().ok_or(Error::EmptyTransaction)?;
+    let txid = transaction_id(raw_data);
+
+    transaction.signature.push(signer.sign_hash(&txid).await?);
+
+    Ok(SignedTransaction { txid, transaction })
+}
+
+/// 验证交易的所有签名，按签名顺序返回签名者的地址
+///
+/// 签名针对 `raw_data` 的 SHA-256 摘要，任一签名无效时返回错误。
+pub fn verify_transaction(transaction: &Transaction) -> Result<Vec<Address>> {
+    let raw_data = transaction.raw_data.as_ref().ok_or(Error::EmptyTransaction)?;
+    let txid = transaction_id(raw_data);
+
+    transaction.signature.iter()
+        .map(|signature| Ok(*PublicKey::recover(&txid, signature)?.address()))
+        .collect()
+}
+
+/// 已经计算好的 SHA-256 摘要
+///
+/// k256 只提供对 [`Digest`](digest::Digest) 签名的接口，同时 RFC 6979 生成随机数时会以同一类型作为 HMAC 的哈希函数。
+/// 因此带有摘要的实例直接输出该摘要，通过 `Default` 创建的实例则与 [`Sha256`] 完全相同。
+#[derive(Clone, Default)]
+pub(crate) struct Prehashed {
+    hash: Option<[u8; 32]>,
+    hasher: Sha256,
+}
+
+impl Prehashed {
+    pub(crate) fn new(hash: &[u8; 32]) -> Self {
+        Self {
+            hash: Some(*hash),
+            hasher: Sha256::default(),
+        }
+    }
+}
+
+impl Update for Prehashed {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.hash = None;
+        self.hasher.update(data);
+    }
+}
+
+impl BlockInput for Prehashed {
+    type BlockSize = U64;
+}
+
+impl FixedOutput for Prehashed {
+    type OutputSize = U32;
+
+    fn finalize_into(self, out: &mut Output<Self>) {
+        match self.hash {
+            Some(hash) => out.copy_from_slice(&hash),
+            None => self.hasher.finalize_into(out),
+        }
+    }
+
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        match self.hash.take() {
+            Some(hash) => out.copy_from_slice(&hash),
+            None => self.hasher.finalize_into_reset(out),
+        }
+    }
+}
+
+impl Reset for Prehashed {
+    fn reset(&mut self) {
+        self.hash = None;
+        self.hasher.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::Digest;
+
+    use super::*;
+    use crate::apis::transaction;
+
+    #[tokio::test]
+    async fn test_sign_hash() {
+        let key = PrivateKey::generate();
+        let data = b"raw data";
+        let hash: [u8; 32] = Sha256::digest(data).into();
+
+        // 与直接对原始数据签名的结果一致
+        assert_eq!(key.sign_hash(&hash).await.unwrap(), key.sign(data).as_ref());
+    }
+
+    #[tokio::test]
+    async fn test_sign_transaction() {
+        let key = PrivateKey::generate();
+        let transaction = Transaction {
+            raw_data: Some(transaction::Raw { timestamp: 1, ..Default::default() }),
+            ..Default::default()
+        };
+
+        let signed = sign_transaction(&key, transaction.clone()).await.unwrap();
+        assert_eq!(signed.txid, transaction_id(transaction.raw_data.as_ref().unwrap()));
+        assert_eq!(signed.transaction.signature, vec![key.sign_hash(&signed.txid).await.unwrap()]);
+
+        assert!(sign_transaction(&key, Transaction::default()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_transaction() {
+        let keys = [PrivateKey::generate(), PrivateKey::generate()];
+        let transaction = Transaction {
+            raw_data: Some(transaction::Raw { timestamp: 1, ..Default::default() }),
+            ..Default::default()
+        };
+
+        let transaction = sign_transaction(&keys[0], transaction).await.unwrap().transaction;
+        let mut transaction = sign_transaction(&keys[1], transaction).await.unwrap().transaction;
+        let signers = verify_transaction(&transaction).unwrap();
+        assert_eq!(signers.iter().collect::<Vec<_>>(), vec![keys[0].address(), keys[1].address()]);
+
+        // 修改交易后恢复出的地址不再是签名者
+        transaction.raw_data.as_mut().unwrap().timestamp = 2;
+        let signers = verify_transaction(&transaction).unwrap_or_default();
+        assert!(!signers.iter().any(|address| address == keys[0].address()));
+
+        transaction.signature[0].truncate(10);
+        assert!(verify_transaction(&transaction).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use prost::Message;
+
+use crate::apis::transaction::contract::ContractType;
+use crate::apis::transaction::{Contract, Raw};
+use crate::apis::*;
+use crate::error::Error;
+use crate::services::TxOptions;
+use crate::Result;
+
+/// 未指定过期时间时，交易在创建后 60 秒过期，与节点的默认行为一致
+pub const DEFAULT_EXPIRATION: i64 = 60_000;
+
+/// 可以放入交易的合约
+pub trait ContractMessage: Message + Sized {
+    const CONTRACT_TYPE: ContractType;
+    /// proto 中的完整类型名，如 `protocol.TransferContract`
+    const TYPE_NAME: &'static str;
+
+    /// 以 `Any` 包装为交易中的合约
+    fn to_contract(&self) -> Contract {
+        let mut value = Vec::with_capacity(self.encoded_len());
+        self.encode(&mut value).unwrap();
+
+        Contract {
+            r#type: Self::CONTRACT_TYPE as i32,
+            parameter: Some(prost_types::Any {
+                type_url: format!("type.googleapis.com/{}", Self::TYPE_NAME),
+                value,
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+macro_rules! contract_message {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl ContractMessage for $name {
+                const CONTRACT_TYPE: ContractType = ContractType::$name;
+                const TYPE_NAME: &'static str = concat!("protocol.", stringify!($name));
+            }
+        )*
+    };
+}
+
+contract_message!(
+    AccountCreateContract,
+    AccountUpdateContract,
+    AccountPermissionUpdateContract,
+    TransferContract,
+    TransferAssetContract,
+    VoteWitnessContract,
+    FreezeBalanceContract,
+    UnfreezeBalanceContract,
+    WithdrawBalanceContract,
+    CreateSmartContract,
+    TriggerSmartContract,
+    UpdateSettingContract,
+    UpdateEnergyLimitContract,
+    ClearAbiContract,
+);
+
+impl From<&BlockExtention> for BlockReference {
+    fn from(block: &BlockExtention) -> Self {
+        BlockReference {
+            block_num: block.block_header.as_ref()
+                .and_then(|header| header.raw_data.as_ref())
+                .map(|raw| raw.number)
+                .unwrap_or_default(),
+            block_hash: block.blockid.clone(),
+        }
+    }
+}
+
+/// 在本地构造交易，不依赖全节点
+///
+/// 生成的 `raw_data` 与节点 `create_transaction2` 等接口创建的交易逐字节一致，可用于冷钱包离线签名。
+/// 引用区块 [`BlockReference`] 需要由调用方提供，通常取最新的固化区块。
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder {
+    raw_data: Raw,
+}
+
+impl TransactionBuilder {
+    pub fn new<C: ContractMessage>(contract: &C, reference: &BlockReference) -> Result<Self> {
+        if reference.block_hash.len() < 16 {
+            return Err(Error::InvalidBlockReference(hex::encode(&reference.block_hash)));
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+
+        Ok(Self {
+            raw_data: Raw {
+                // 区块高度的第 6、7 字节和区块哈希的第 8 ~ 15 字节
+                ref_block_bytes: reference.block_num.to_be_bytes()[6..8].to_vec(),
+                ref_block_hash: reference.block_hash[8..16].to_vec(),
+                contract: vec![contract.to_contract()],
+                timestamp,
+                ..Default::default()
+            },
+        })
+    }
+
+    /// 交易创建时间（毫秒时间戳），默认为当前时间
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.raw_data.timestamp = timestamp;
+        self
+    }
+
+    /// 交易过期时间（毫秒时间戳），默认为创建时间之后 [`DEFAULT_EXPIRATION`]
+    pub fn expiration(mut self, expiration: i64) -> Self {
+        self.raw_data.expiration = expiration;
+        self
+    }
+
+    /// 应用交易选项，合约调用未设置手续费上限时使用默认值，选项不适用于该交易时返回 [`Error::InvalidOptions`]
+    pub fn options(mut self, options: &TxOptions) -> Result<Self> {
+        options.apply(&mut self.raw_data)?;
+        Ok(self)
+    }
+
+    pub fn build_raw(mut self) -> Raw {
+        if self.raw_data.expiration == 0 {
+            self.raw_data.expiration = self.raw_data.timestamp + DEFAULT_EXPIRATION;
+        }
+        self.raw_data
+    }
+
+    /// 构造未签名的交易
+    pub fn build(self) -> Transaction {
+        Transaction {
+            raw_data: Some(self.build_raw()),
+            ..Default::default()
+        }
+    }
+}
+
+/// 已签名、尚未广播的交易
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedTransaction {
+    /// 交易 ID，即 `raw_data` 编码后的 SHA-256，在本地计算
+    pub txid: [u8; 32],
+    pub transaction: Transaction,
+}
+
+impl SignedTransaction {
+    pub fn txid_hex(&self) -> String {
+        hex::encode(self.txid)
+    }
+}
+
+/// 交易广播的回执
+#[derive(Debug, Clone, PartialEq)]
+pub struct BroadcastReceipt {
+    /// 交易 ID，即 `raw_data` 编码后的 SHA-256，在本地计算
+    pub txid: [u8; 32],
+    pub signed_transaction: Transaction,
+    /// 节点返回的广播结果
+    pub r#return: Return,
+}
+
+impl BroadcastReceipt {
+    pub fn txid_hex(&self) -> String {
+        hex::encode(self.txid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::transaction_id;
+
+    // 引用区块高度 0x175e4b，哈希的第 8 ~ 15 字节为 47c9dc89341b300d
+    const BLOCK_HASH: &str = "0000000000175e4b47c9dc89341b300dc0ffee0000000000000000000000000000";
+    const TIMESTAMP: i64 = 1591089567635;
+    const EXPIRATION: i64 = 1591089627000;
+
+    const OWNER: &str = "41608f8da72479edc7dd921e4c30bb7e7cddbe722e";
+    const TO: &str = "41e9d79cc47518930bc322d9bf7cddd260a0260a8d";
+    const USDT: &str = "41a614f803b6fd780986a42c78ec9c7f77e6ded13c";
+
+    // TRON 开发者文档中 TransferContract 交易的 raw_data_hex
+    const TRANSFER_FIXTURE: &str = "0a025e4b220847c9dc89341b300d40f8fed3a2a72e5a66080112620a2d747970652e676f6f676c65617069732e636f6d2f70726f746f636f6c2e5472616e73666572436f6e747261637412310a1541608f8da72479edc7dd921e4c30bb7e7cddbe722e121541e9d79cc47518930bc322d9bf7cddd260a0260a8d18e8077093afd0a2a72e";
+    const TRANSFER_TXID: &str = "77ddfa7093cc5f745c0d3a54abb89ef070f983343c05e0f89e5a52f3e5401299";
+    const TRIGGER_FIXTURE: &str = "0a025e4b220847c9dc89341b300d40f8fed3a2a72e5aae01081f12a9010a31747970652e676f6f676c65617069732e636f6d2f70726f746f636f6c2e54726967676572536d617274436f6e747261637412740a1541608f8da72479edc7dd921e4c30bb7e7cddbe722e121541a614f803b6fd780986a42c78ec9c7f77e6ded13c2244a9059cbb000000000000000000000000e9d79cc47518930bc322d9bf7cddd260a0260a8d00000000000000000000000000000000000000000000000000000000000f42407093afd0a2a72e900180ade204";
+    const FREEZE_FIXTURE: &str = "0a025e4b220847c9dc89341b300d40f8fed3a2a72e5a5a080b12560a32747970652e676f6f676c65617069732e636f6d2f70726f746f636f6c2e467265657a6542616c616e6365436f6e747261637412200a1541608f8da72479edc7dd921e4c30bb7e7cddbe722e1080ade204180350017093afd0a2a72e";
+
+    fn reference() -> BlockReference {
+        BlockReference {
+            block_num: 0x175e4b,
+            block_hash: hex::decode(BLOCK_HASH).unwrap(),
+        }
+    }
+
+    fn encode(raw_data: &Raw) -> String {
+        let mut buf = Vec::new();
+        raw_data.encode(&mut buf).unwrap();
+        hex::encode(buf)
+    }
+
+    #[test]
+    fn test_transfer() {
+        let raw_data = TransactionBuilder::new(&TransferContract {
+            owner_address: hex::decode(OWNER).unwrap(),
+            to_address: hex::decode(TO).unwrap(),
+            amount: 1000,
+        }, &reference())
+            .unwrap()
+            .timestamp(TIMESTAMP)
+            .expiration(EXPIRATION)
+            .build_raw();
+
+        assert_eq!(encode(&raw_data), TRANSFER_FIXTURE);
+        assert_eq!(hex::encode(transaction_id(&raw_data)), TRANSFER_TXID);
+    }
+
+    #[test]
+    fn test_trigger_smart_contract() {
+        let raw_data = TransactionBuilder::new(&TriggerSmartContract {
+            owner_address: hex::decode(OWNER).unwrap(),
+            contract_address: hex::decode(USDT).unwrap(),
+            data: hex::decode(format!("a9059cbb000000000000000000000000{}{:064x}", &TO[2..], 1_000_000)).unwrap(),
+            ..Default::default()
+        }, &reference())
+            .unwrap()
+            .timestamp(TIMESTAMP)
+            .expiration(EXPIRATION)
+            .options(&TxOptions::new())
+            .unwrap()
+            .build_raw();
+
+        assert_eq!(encode(&raw_data), TRIGGER_FIXTURE);
+    }
+
+    #[test]
+    fn test_freeze_balance() {
+        let raw_data = TransactionBuilder::new(&FreezeBalanceContract {
+            owner_address: hex::decode(OWNER).unwrap(),
+            frozen_balance: 10_000000,
+            frozen_duration: 3,
+            resource: ResourceCode::Energy as i32,
+            receiver_address: vec![],
+        }, &reference())
+            .unwrap()
+            .timestamp(TIMESTAMP)
+            .expiration(EXPIRATION)
+            .build_raw();
+
+        assert_eq!(encode(&raw_data), FREEZE_FIXTURE);
+    }
+
+    #[test]
+    fn test_default_expiration() {
+        let raw_data = TransactionBuilder::new(&TransferContract::default(), &reference())
+            .unwrap()
+            .timestamp(TIMESTAMP)
+            .build_raw();
+
+        assert_eq!(raw_data.expiration, TIMESTAMP + DEFAULT_EXPIRATION);
+        assert!(TransactionBuilder::new(&TransferContract::default(), &BlockReference::default()).is_err());
+    }
+}
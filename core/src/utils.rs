@@ -1,5 +1,8 @@
+use prost::Message;
 use sha2::Digest;
 
+use crate::apis::transaction;
+
 pub fn to_raw_address<T: AsRef<[u8]>>(checked_address: &T) -> &[u8] {
     &checked_address.as_ref()[0..21]
 }
@@ -11,4 +14,14 @@ pub fn raw_address_base_check(raw_address: &[u8]) -> [u8; 4] {
 
     buf.copy_from_slice(&h2[0..4]);
     buf
-}
\ No newline at end of file
+}
+
+/// 交易 ID，即 `raw_data` 编码后的 SHA-256
+pub fn transaction_id(raw_data: &transaction::Raw) -> [u8; 32] {
+    let mut buf = Vec::with_capacity(raw_data.encoded_len());
+    raw_data.encode(&mut buf).unwrap();
+
+    let mut id = [0; 32];
+    id.copy_from_slice(&sha2::Sha256::digest(&buf));
+    id
+}
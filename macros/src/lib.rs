@@ -0,0 +1,537 @@
+//! 由合约 ABI 生成强类型绑定的过程宏
+//!
+//! ```ignore
+//! tron_contract!(Usdt, "abi/usdt.json");
+//!
+//! let usdt = Usdt::new(address);
+//! let balance = usdt.balance_of(&agent, owner).await?;
+//! usdt.transfer(&mut agent, to, amount, &TxOptions::default()).await?;
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr, Token};
+use tron_core::contract::parse_abi;
+use tron_core::ethabi::param_type::Writer;
+use tron_core::ethabi::{Contract, Event, Function, ParamType, StateMutability};
+
+/// 生成合约绑定
+///
+/// 参数为 ABI 文件路径（相对于调用方 crate 的 `Cargo.toml`），可以在前面指定结构体名，
+/// 省略时由文件名转换而来，如 `usdt.json` 生成 `Usdt`。
+///
+/// - 只读函数生成 `async fn name(&self, agent: &ServiceAgent<'_, S>, ...) -> Result<T>`；
+/// - 其余函数生成 `async fn name(&self, agent: &mut ServiceAgent<'_, S>, ..., options: &TxOptions) -> Result<BroadcastReceipt>`；
+/// - 每个事件生成 `<结构体名><事件名>Event`，用 `decode` 从交易日志中解码。
+///
+/// `address` 映射为 TRON 的 `Address`，整数映射为 `Uint`，`bytes` 和 `bytesN` 映射为 `Vec<u8>`，
+/// 数组映射为 `Vec<T>`，元组映射为 Rust 元组。重载的函数和事件从第二个起加上序号后缀，
+/// 与 `new`、`address`、`contract` 或其他方法重名的函数加上 `_` 后缀。
+#[proc_macro]
+pub fn tron_contract(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as ContractInput);
+
+    expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+struct ContractInput {
+    name: Option<Ident>,
+    path: LitStr,
+}
+
+impl Parse for ContractInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = if input.peek(Ident) {
+            let name = input.parse()?;
+            input.parse::<Token![,]>()?;
+            Some(name)
+        } else {
+            None
+        };
+        let path = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+
+        Ok(Self { name, path })
+    }
+}
+
+fn expand(input: ContractInput) -> syn::Result<TokenStream> {
+    let error = |message: String| syn::Error::new(input.path.span(), message);
+
+    let root = std::env::var("CARGO_MANIFEST_DIR").map_err(|e| error(e.to_string()))?;
+    let path = PathBuf::from(root).join(input.path.value());
+    let json = std::fs::read_to_string(&path).map_err(|e| error(format!("read {}: {}", path.display(), e)))?;
+    let abi = parse_abi(&json).map_err(|e| error(format!("parse {}: {}", path.display(), e)))?;
+
+    let name = match input.name {
+        Some(name) => name,
+        None => {
+            let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+            syn::parse_str(&camel_case(stem)).map_err(|_| error(format!("cannot name contract from {}", stem)))?
+        }
+    };
+
+    Ok(generate(&name, &path.display().to_string(), &abi))
+}
+
+fn generate(name: &Ident, path: &str, abi: &Contract) -> TokenStream {
+    let functions = sorted(&abi.functions, |function| function.signature());
+    let functions = functions.iter()
+        .zip(method_names(&functions))
+        .map(|((function, _), method)| generate_function(function, method));
+    let events = sorted(&abi.events, |event| event.signature().to_string())
+        .into_iter()
+        .map(|(event, suffix)| generate_event(name, event, abi, suffix));
+
+    quote! {
+        #[derive(Debug, Clone)]
+        pub struct #name {
+            contract: ::tron_core::contract::Contract,
+        }
+
+        // 与 tron_core 相同，允许较大的错误类型
+        #[allow(clippy::result_large_err)]
+        impl #name {
+            /// 生成绑定时使用的 JSON ABI
+            pub const ABI: &'static str = include_str!(#path);
+
+            pub fn new(address: ::tron_core::key::Address) -> Self {
+                let contract = ::tron_core::contract::Contract::from_json(address, Self::ABI)
+                    .expect("ABI is checked when generating the binding");
+
+                Self { contract }
+            }
+
+            pub fn address(&self) -> &::tron_core::key::Address {
+                self.contract.address()
+            }
+
+            pub fn contract(&self) -> &::tron_core::contract::Contract {
+                &self.contract
+            }
+
+            #(#functions)*
+        }
+
+        #(#events)*
+    }
+}
+
+/// 按名称和签名排序，重载的条目从第二个起加上序号后缀
+fn sorted<T>(entries: &HashMap<String, Vec<T>>, signature: impl Fn(&T) -> String) -> Vec<(&T, String)> {
+    let mut names: Vec<_> = entries.keys().collect();
+    names.sort();
+
+    names.into_iter()
+        .flat_map(|name| {
+            let mut overloads: Vec<_> = entries[name].iter().collect();
+            overloads.sort_by_key(|entry| signature(entry));
+            overloads.into_iter()
+                .enumerate()
+                .map(|(index, entry)| (entry, if index == 0 { String::new() } else { (index + 1).to_string() }))
+        })
+        .collect()
+}
+
+/// 绑定自身的方法，合约函数不能使用这些名称
+const RESERVED_METHODS: [&str; 3] = ["new", "address", "contract"];
+
+/// 合约函数对应的方法名
+///
+/// 与 [`RESERVED_METHODS`] 或其他方法重名时加上 `_` 后缀，没有序号后缀的名称优先，
+/// 如重载的 `transfer` 生成的 `transfer2` 与合约中的 `transfer2` 重名时前者改为 `transfer2_`。
+fn method_names(functions: &[(&Function, String)]) -> Vec<Ident> {
+    let mut used: HashSet<String> = RESERVED_METHODS.iter().map(|name| name.to_string()).collect();
+    let mut methods = vec![None; functions.len()];
+
+    for suffixed in [false, true] {
+        for (index, (function, suffix)) in functions.iter().enumerate() {
+            if suffix.is_empty() == suffixed {
+                continue;
+            }
+            let mut method = ident(&format!("{}{}", snake_case(&function.name), suffix));
+            while !used.insert(method.to_string()) {
+                method = format_ident!("{}_", method);
+            }
+            methods[index] = Some(method);
+        }
+    }
+
+    methods.into_iter().flatten().collect()
+}
+
+fn generate_function(function: &Function, method: Ident) -> TokenStream {
+    let signature = format!(
+        "{}({})",
+        function.name,
+        function.inputs.iter().map(|param| Writer::write(&param.kind)).collect::<Vec<_>>().join(",")
+    );
+    let doc = format!("调用合约函数 `{}`", signature);
+
+    let args: Vec<_> = function.inputs.iter()
+        .enumerate()
+        .map(|(index, param)| param_ident(&param.name, index))
+        .collect();
+    let types = function.inputs.iter().map(|param| rust_type(&param.kind));
+    let tokens = function.inputs.iter().zip(&args).map(|(param, arg)| tokenize(&param.kind, quote!(#arg), 0));
+
+    let is_constant = matches!(function.state_mutability, StateMutability::View | StateMutability::Pure);
+    if is_constant {
+        let output_types = function.outputs.iter().map(|param| rust_type(&param.kind));
+        let output_type = match function.outputs.len() {
+            1 => quote!(#(#output_types)*),
+            _ => quote!((#(#output_types,)*)),
+        };
+        let call = quote!(self.contract.call(agent, #signature, &[#(#tokens),*]).await?);
+        let kinds: Vec<_> = function.outputs.iter().map(|param| param.kind.clone()).collect();
+        let values = detokenize_values(&kinds);
+        let body = match values.len() {
+            0 => quote! {
+                #call;
+                Ok(())
+            },
+            len => {
+                let output = if len == 1 { quote!(#(#values)*) } else { quote!((#(#values,)*)) };
+                quote! {
+                    let __invalid = || ::tron_core::Error::from(::tron_core::ethabi::Error::InvalidData);
+                    let mut __tokens = #call.into_iter();
+
+                    Ok(#output)
+                }
+            }
+        };
+
+        quote! {
+            #[doc = #doc]
+            pub async fn #method<S: ::tron_core::signer::Signer>(
+                &self,
+                agent: &::tron_core::services::ServiceAgent<'_, S>,
+                #(#args: #types,)*
+            ) -> ::tron_core::Result<#output_type> {
+                #body
+            }
+        }
+    } else {
+        quote! {
+            #[doc = #doc]
+            pub async fn #method<S: ::tron_core::signer::Signer>(
+                &self,
+                agent: &mut ::tron_core::services::ServiceAgent<'_, S>,
+                #(#args: #types,)*
+                options: &::tron_core::services::TxOptions,
+            ) -> ::tron_core::Result<::tron_core::transaction::BroadcastReceipt> {
+                self.contract.send(agent, #signature, &[#(#tokens),*], options).await
+            }
+        }
+    }
+}
+
+fn generate_event(contract: &Ident, event: &Event, abi: &Contract, suffix: String) -> TokenStream {
+    let name = format_ident!("{}{}{}Event", contract, camel_case(&event.name), suffix);
+    let doc = format!("合约事件 `{}`", event.name);
+    let index = abi.events[&event.name].iter().position(|e| e == event).unwrap_or_default();
+    let event_name = &event.name;
+
+    let fields: Vec<_> = event.inputs.iter()
+        .enumerate()
+        .map(|(index, param)| param_ident(&param.name, index))
+        .collect();
+    let types = event.inputs.iter().map(|param| rust_type(&param.kind));
+    let kinds: Vec<_> = event.inputs.iter().map(|param| param.kind.clone()).collect();
+    let values = detokenize_values(&kinds);
+    let (tokens, prologue) = match values.len() {
+        0 => (quote!(_tokens), quote!()),
+        _ => (quote!(tokens), quote! {
+            let __invalid = || ::tron_core::Error::from(::tron_core::ethabi::Error::InvalidData);
+            let mut __tokens = tokens.into_iter();
+        }),
+    };
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct #name {
+            #(pub #fields: #types,)*
+        }
+
+        #[allow(clippy::result_large_err)]
+        impl #name {
+            /// 从 `TransactionInfo.log` 中解码，不是该事件时返回 `None`
+            pub fn decode(log: &::tron_core::apis::transaction_info::Log) -> Option<Self> {
+                let abi = ::tron_core::contract::parse_abi(#contract::ABI).ok()?;
+                let event = abi.events_by_name(#event_name).ok()?.get(#index)?;
+
+                Self::from_tokens(::tron_core::contract::decode_log(event, log)?).ok()
+            }
+
+            /// 由解码后的事件参数创建
+            pub fn from_tokens(#tokens: Vec<::tron_core::ethabi::Token>) -> ::tron_core::Result<Self> {
+                #prologue
+                Ok(Self { #(#fields: #values,)* })
+            }
+        }
+    }
+}
+
+/// 依次从迭代器 `__tokens` 中取出并转换每个值的表达式
+fn detokenize_values(kinds: &[ParamType]) -> Vec<TokenStream> {
+    kinds.iter()
+        .map(|kind| {
+            let detokenize = detokenize(kind, 0);
+            quote! {{
+                let t0 = __tokens.next().ok_or_else(__invalid)?;
+                #detokenize?
+            }}
+        })
+        .collect()
+}
+
+/// 参数类型对应的 Rust 类型
+fn rust_type(kind: &ParamType) -> TokenStream {
+    match kind {
+        ParamType::Address => quote!(::tron_core::key::Address),
+        ParamType::Uint(_) | ParamType::Int(_) => quote!(::tron_core::ethabi::Uint),
+        ParamType::Bool => quote!(bool),
+        ParamType::String => quote!(String),
+        ParamType::Bytes | ParamType::FixedBytes(_) => quote!(Vec<u8>),
+        ParamType::Array(kind) | ParamType::FixedArray(kind, _) => {
+            let kind = rust_type(kind);
+            quote!(Vec<#kind>)
+        }
+        ParamType::Tuple(kinds) => {
+            let kinds = kinds.iter().map(rust_type);
+            quote!((#(#kinds,)*))
+        }
+    }
+}
+
+/// 把 `value` 转换为 `Token` 的表达式，`depth` 用于区分嵌套闭包中的变量
+fn tokenize(kind: &ParamType, value: TokenStream, depth: usize) -> TokenStream {
+    let token = quote!(::tron_core::ethabi::Token);
+
+    match kind {
+        ParamType::Address => quote!(#token::Address(#value.into())),
+        ParamType::Uint(_) => quote!(#token::Uint(#value)),
+        ParamType::Int(_) => quote!(#token::Int(#value)),
+        ParamType::Bool => quote!(#token::Bool(#value)),
+        ParamType::String => quote!(#token::String(#value)),
+        ParamType::Bytes => quote!(#token::Bytes(#value)),
+        ParamType::FixedBytes(_) => quote!(#token::FixedBytes(#value)),
+        ParamType::Array(kind) => {
+            let items = tokenize_items(kind, value, depth);
+            quote!(#token::Array(#items))
+        }
+        ParamType::FixedArray(kind, _) => {
+            let items = tokenize_items(kind, value, depth);
+            quote!(#token::FixedArray(#items))
+        }
+        ParamType::Tuple(kinds) => {
+            let items: Vec<_> = (0..kinds.len()).map(|index| format_ident!("v{}_{}", depth, index)).collect();
+            let inner = kinds.iter().zip(&items).map(|(kind, item)| tokenize(kind, quote!(#item), depth + 1));
+            quote!({
+                let (#(#items,)*) = #value;
+                #token::Tuple(vec![#(#inner),*])
+            })
+        }
+    }
+}
+
+fn tokenize_items(kind: &ParamType, value: TokenStream, depth: usize) -> TokenStream {
+    let item = format_ident!("v{}", depth);
+    let inner = tokenize(kind, quote!(#item), depth + 1);
+
+    quote!(#value.into_iter().map(|#item| #inner).collect())
+}
+
+/// 把变量 `t<depth>` 中的 `Token` 转换为 Rust 值的表达式，结果为 `Result`，类型不符时返回 `__invalid()`
+fn detokenize(kind: &ParamType, depth: usize) -> TokenStream {
+    let token = quote!(::tron_core::ethabi::Token);
+    let t = format_ident!("t{}", depth);
+    let into = |method: &str| {
+        let method = format_ident!("{}", method);
+        quote!(#t.#method().ok_or_else(__invalid))
+    };
+
+    match kind {
+        ParamType::Address => quote! {
+            match #t {
+                #token::Address(address) => Ok(::tron_core::key::Address::from(address)),
+                _ => Err(__invalid()),
+            }
+        },
+        ParamType::Uint(_) => into("into_uint"),
+        ParamType::Int(_) => into("into_int"),
+        ParamType::Bool => into("into_bool"),
+        ParamType::String => into("into_string"),
+        ParamType::Bytes => into("into_bytes"),
+        ParamType::FixedBytes(_) => into("into_fixed_bytes"),
+        ParamType::Array(kind) | ParamType::FixedArray(kind, _) => {
+            let item = format_ident!("t{}", depth + 1);
+            let inner = detokenize(kind, depth + 1);
+            quote! {
+                match #t {
+                    #token::Array(items) | #token::FixedArray(items) => items.into_iter()
+                        .map(|#item| #inner)
+                        .collect::<::tron_core::Result<Vec<_>>>(),
+                    _ => Err(__invalid()),
+                }
+            }
+        }
+        ParamType::Tuple(kinds) => {
+            let item = format_ident!("t{}", depth + 1);
+            let len = kinds.len();
+            let inner = kinds.iter().map(|kind| {
+                let inner = detokenize(kind, depth + 1);
+                quote! {{
+                    let #item = items.next().ok_or_else(__invalid)?;
+                    #inner?
+                }}
+            });
+            quote! {
+                match #t {
+                    #token::Tuple(items) if items.len() == #len => {
+                        let mut items = items.into_iter();
+                        Ok((#(#inner,)*))
+                    }
+                    _ => Err(__invalid()),
+                }
+            }
+        }
+    }
+}
+
+/// 参数名转换为标识符，未命名的参数按位置命名为 `arg<index>`
+fn param_ident(name: &str, index: usize) -> Ident {
+    match snake_case(name) {
+        name if name.is_empty() => format_ident!("arg{}", index),
+        // 避免与生成代码中的参数重名
+        name if name == "agent" || name == "options" => format_ident!("{}_", name),
+        name => ident(&name),
+    }
+}
+
+/// 与关键字冲突的名称加上 `_` 后缀
+fn ident(name: &str) -> Ident {
+    syn::parse_str(name).unwrap_or_else(|_| Ident::new(&format!("{}_", name), Span::call_site()))
+}
+
+/// `balanceOf` → `balance_of`，`DOMAIN_SEPARATOR` → `domain_separator`
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && index > 0 {
+            let prev = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(|next| next.is_ascii_lowercase());
+            if prev.is_ascii_lowercase() || prev.is_ascii_digit() || (prev.is_ascii_uppercase() && next_is_lower) {
+                snake.push('_');
+            }
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+
+    snake
+}
+
+/// `trc20_token` → `Trc20Token`
+fn camel_case(name: &str) -> String {
+    name.split(['_', '-', '.'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABI: &str = r#"[
+        {"type": "function", "name": "balanceOf", "inputs": [{"name": "owner", "type": "address"}],
+         "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"},
+        {"type": "function", "name": "getReserves", "inputs": [],
+         "outputs": [{"name": "", "type": "uint112"}, {"name": "", "type": "tuple[]",
+            "components": [{"name": "a", "type": "address"}, {"name": "b", "type": "bytes32"}]}], "stateMutability": "view"},
+        {"type": "function", "name": "transfer", "inputs": [{"name": "to", "type": "address"}, {"name": "value", "type": "uint256"}],
+         "outputs": [{"name": "", "type": "bool"}], "stateMutability": "nonpayable"},
+        {"type": "function", "name": "transfer", "inputs": [{"name": "to", "type": "address[2]"}, {"name": "options", "type": "uint256"}],
+         "outputs": [], "stateMutability": "nonpayable"},
+        {"type": "event", "name": "Transfer", "anonymous": false, "inputs": [
+            {"name": "from", "type": "address", "indexed": true},
+            {"name": "to", "type": "address", "indexed": true},
+            {"name": "value", "type": "uint256", "indexed": false}]}
+    ]"#;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(snake_case("balanceOf"), "balance_of");
+        assert_eq!(snake_case("DOMAIN_SEPARATOR"), "domain_separator");
+        assert_eq!(snake_case("getERC20Balance"), "get_erc20_balance");
+        assert_eq!(camel_case("trc20_token"), "Trc20Token");
+        assert_eq!(param_ident("type", 0), "type_");
+        assert_eq!(param_ident("", 1), "arg1");
+        assert_eq!(param_ident("options", 1), "options_");
+    }
+
+    #[test]
+    fn test_method_names() {
+        let abi = parse_abi(r#"[
+            {"type": "function", "name": "address", "inputs": [], "outputs": [], "stateMutability": "view"},
+            {"type": "function", "name": "contract", "inputs": [], "outputs": [], "stateMutability": "view"},
+            {"type": "function", "name": "new", "inputs": [], "outputs": [], "stateMutability": "nonpayable"},
+            {"type": "function", "name": "type", "inputs": [], "outputs": [], "stateMutability": "view"},
+            {"type": "function", "name": "transfer", "inputs": [{"name": "to", "type": "address"}], "outputs": []},
+            {"type": "function", "name": "transfer", "inputs": [{"name": "to", "type": "address[]"}], "outputs": []},
+            {"type": "function", "name": "transfer2", "inputs": [], "outputs": []}
+        ]"#).unwrap();
+        let functions = sorted(&abi.functions, |function| function.signature());
+        let methods: Vec<_> = functions.iter()
+            .zip(method_names(&functions))
+            .map(|((function, _), method)| (function.signature(), method.to_string()))
+            .collect();
+
+        let method = |signature: &str| methods.iter()
+            .find(|(s, _)| s.starts_with(signature))
+            .map(|(_, method)| method.as_str())
+            .unwrap();
+        assert_eq!(method("address()"), "address_");
+        assert_eq!(method("contract()"), "contract_");
+        assert_eq!(method("new()"), "new_");
+        assert_eq!(method("type()"), "type_");
+        assert_eq!(method("transfer(address)"), "transfer");
+        assert_eq!(method("transfer(address[])"), "transfer2_");
+        assert_eq!(method("transfer2()"), "transfer2");
+
+        let code = generate(&format_ident!("Token"), "abi/token.json", &abi).to_string();
+        assert!(code.contains("pub fn address (& self)"));
+        assert!(code.contains("pub async fn address_ <"));
+    }
+
+    #[test]
+    fn test_generate() {
+        let abi = parse_abi(ABI).unwrap();
+        let code = generate(&format_ident!("Token"), "abi/token.json", &abi);
+        let file: syn::File = syn::parse2(code).expect("generated code should be valid Rust");
+
+        let code = quote!(#file).to_string();
+        for expected in [
+            "pub struct Token",
+            "pub async fn balance_of",
+            "pub async fn get_reserves",
+            "pub async fn transfer <",
+            "pub async fn transfer2 <",
+            "\"transfer(address[2],uint256)\"",
+            "options_ : :: tron_core :: ethabi :: Uint",
+            "pub struct TokenTransferEvent",
+            "pub from : :: tron_core :: key :: Address",
+        ] {
+            assert!(code.contains(expected), "missing {}", expected);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// 与 tron_core 相同，模拟节点的处理函数返回 tonic::Status
+#![allow(clippy::result_large_err)]
+
+use std::sync::{Arc, Mutex};
+
+use tron_core::apis::transaction_info::Log;
+use tron_core::apis::{transaction, Return, Transaction, TransactionExtention, TriggerSmartContract};
+use tron_core::ethabi::{self, Token};
+use tron_core::key::{Address, PrivateKey};
+use tron_core::services::mock::MockWallet;
+use tron_core::services::{Service, ServiceConfig, TxOptions};
+use tron_macros::tron_contract;
+
+// 结构体名由文件名生成
+tron_contract!("tests/abi/simple_token.json");
+
+fn token() -> Address {
+    "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".parse().unwrap()
+}
+
+fn owner() -> Address {
+    Address::from_evm([0x22; 20])
+}
+
+fn created_transaction(_: TriggerSmartContract) -> Result<TransactionExtention, tonic::Status> {
+    Ok(TransactionExtention {
+        transaction: Some(Transaction {
+            raw_data: Some(transaction::Raw { timestamp: 1, ..Default::default() }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_generated_contract() {
+    let to = Address::from_evm([0x11; 20]);
+    let triggered = Arc::new(Mutex::new(None));
+    let trigger = triggered.clone();
+    let endpoint = MockWallet::new()
+        .unary("TriggerConstantContract", |request: TriggerSmartContract| {
+            assert_eq!(request.contract_address, Vec::from(token()));
+
+            let output = match hex::encode(&request.data[..4]).as_str() {
+                "70a08231" => {
+                    assert_eq!(&request.data[16..36], &owner().evm_address());
+                    ethabi::encode(&[Token::Uint(42.into())])
+                }
+                "8da5cb5b" => ethabi::encode(&[owner().into()]),
+                _ => vec![],
+            };
+            Ok(TransactionExtention { constant_result: vec![output], ..Default::default() })
+        })
+        .unary("TriggerContract", move |request: TriggerSmartContract| {
+            *trigger.lock().unwrap() = Some(request.data.clone());
+            created_transaction(request)
+        })
+        .unary("BroadcastTransaction", |_: Transaction| Ok(Return { result: true, ..Default::default() }))
+        .serve()
+        .await;
+
+    let service = Service::with_config(ServiceConfig::new(endpoint)).await.unwrap();
+    let mut agent = service.agent(PrivateKey::generate());
+    let contract = SimpleToken::new(token());
+    // ABI 中的 `address()` 生成为 `address_`，不与绑定自身的方法冲突
+    assert_eq!(contract.address(), &token());
+
+    assert_eq!(contract.balance_of(&agent, owner()).await.unwrap(), 42.into());
+    assert_eq!(contract.owner(&agent).await.unwrap(), owner());
+
+    let receipt = contract.transfer(&mut agent, to, 100.into(), &TxOptions::default()).await.unwrap();
+    assert!(receipt.r#return.result);
+    let data = triggered.lock().unwrap().take().unwrap();
+    assert_eq!(data, contract.contract().encode_input("transfer", &[to.into(), Token::Uint(100.into())]).unwrap());
+}
+
+#[test]
+fn test_generated_event() {
+    let topic = |address: Address| {
+        let mut topic = vec![0; 12];
+        topic.extend(address.evm_address());
+        topic
+    };
+    let event = SimpleToken::new(token()).contract().abi().event("Transfer").unwrap().signature();
+    let log = Log {
+        address: token().evm_address().to_vec(),
+        topics: vec![event.as_bytes().to_vec(), topic(owner()), topic(Address::from_evm([0x11; 20]))],
+        data: ethabi::encode(&[Token::Uint(7.into())]),
+    };
+
+    let transfer = SimpleTokenTransferEvent::decode(&log).unwrap();
+    assert_eq!(transfer.from, owner());
+    assert_eq!(transfer.to, Address::from_evm([0x11; 20]));
+    assert_eq!(transfer.value, 7.into());
+}